@@ -0,0 +1,443 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::audio_capture::{AudioState, TARGET_SAMPLE_RATE};
+use crate::gemini_client::{pcm16, wav_header};
+use crate::session_manager::SessionManager;
+
+// ============================================================================
+// LOCAL AUDIO ARCHIVE - opt-in WAV recording of each session
+// ============================================================================
+
+/// Recorder slot shared between the commands and the running mixer
+pub type ArchiveSlot = Arc<Mutex<Option<SessionRecorder>>>;
+
+/// Rewrite the header this often so a crash leaves a playable file
+const HEADER_REFRESH_SECS: u32 = 5;
+const MAX_SEGMENT_SECS: u32 = 60 * 60;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    pub segment_secs: u32,   // Rollover to a new file after this much audio
+    pub retention_days: u32, // 0 = keep forever
+    pub max_archive_mb: u64, // Total budget across all sessions, 0 = unlimited
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            segment_secs: 600,
+            retention_days: 30,
+            max_archive_mb: 2048,
+        }
+    }
+}
+
+// ============================================================================
+// STREAMING WAV WRITER
+// ============================================================================
+
+/// 16 kHz mono 16-bit WAV written incrementally; sizes are patched on finalize
+pub struct WavStreamWriter {
+    file: BufWriter<File>,
+    path: PathBuf,
+    samples: u32,
+    since_refresh: u32,
+}
+
+impl WavStreamWriter {
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&wav_header(0))?;
+        Ok(Self { file, path: path.to_path_buf(), samples: 0, since_refresh: 0 })
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> std::io::Result<()> {
+        for s in samples {
+            self.file.write_all(&pcm16(*s))?;
+        }
+        self.samples = self.samples.saturating_add(samples.len() as u32);
+        self.since_refresh += samples.len() as u32;
+        if self.since_refresh >= HEADER_REFRESH_SECS * TARGET_SAMPLE_RATE {
+            self.since_refresh = 0;
+            self.patch_header()?;
+        }
+        Ok(())
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn patch_header(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let file = self.file.get_mut();
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&wav_header(self.samples))?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    /// Writes the final RIFF/data sizes and syncs the file to disk
+    pub fn finalize(mut self) -> std::io::Result<PathBuf> {
+        self.patch_header()?;
+        self.file.get_ref().sync_all()?;
+        Ok(self.path.clone())
+    }
+}
+
+// ============================================================================
+// SESSION RECORDER
+// ============================================================================
+
+pub struct SessionRecorder {
+    session_id: String,
+    dir: PathBuf,
+    config: ArchiveConfig,
+    writer: Option<WavStreamWriter>,
+    segment: u32,
+    files: Vec<PathBuf>,
+    closing: Vec<JoinHandle<Result<(), String>>>, // Segments still being finalized
+}
+
+impl SessionRecorder {
+    pub fn new(manager: &SessionManager, session_id: &str, config: ArchiveConfig) -> Result<Self, String> {
+        validate_session_id(session_id)?;
+        Self::in_dir(manager.audio_dir(session_id), session_id, config)
+    }
+
+    fn in_dir(dir: PathBuf, session_id: &str, config: ArchiveConfig) -> Result<Self, String> {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create audio directory: {}", e))?;
+
+        // Continue numbering after any parts left by an earlier run of this session
+        let segment = fs::read_dir(&dir)
+            .map(|entries| entries.flatten().filter(|e| is_archived_audio(&e.path())).count() as u32)
+            .unwrap_or(0);
+
+        Ok(Self {
+            session_id: session_id.to_string(),
+            dir,
+            config,
+            writer: None,
+            segment,
+            files: Vec::new(),
+            closing: Vec::new(),
+        })
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let limit = self.config.segment_secs.clamp(1, MAX_SEGMENT_SECS) * TARGET_SAMPLE_RATE;
+        if self.writer.as_ref().is_some_and(|w| w.samples() >= limit) {
+            self.close_segment();
+        }
+        if self.writer.is_none() {
+            let path = self.dir.join(format!("part-{:03}.wav", self.segment));
+            self.segment += 1;
+            let writer = WavStreamWriter::create(&path)
+                .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            println!("[ARCHIVE] Recording to {}", path.display());
+            self.writer = Some(writer);
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write(samples).map_err(|e| format!("Failed to write audio: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Hands the open segment to a background thread for the header patch, fsync
+    /// and retention pass, so a rollover never stalls the mixer
+    fn close_segment(&mut self) {
+        let Some(writer) = self.writer.take() else { return };
+        self.files.push(writer.path().to_path_buf());
+
+        let sessions_dir = self.dir.parent().and_then(Path::parent).map(Path::to_path_buf);
+        let config = self.config;
+        let session_id = self.session_id.clone();
+        self.closing.retain(|handle| !handle.is_finished());
+        self.closing.push(thread::spawn(move || {
+            writer.finalize().map_err(|e| format!("Failed to finalize audio: {}", e))?;
+            if let Some(sessions_dir) = sessions_dir {
+                enforce_retention(&sessions_dir, &config, Some(&session_id));
+            }
+            Ok(())
+        }));
+    }
+
+    /// Finalizes the open segment, waits for every pending close and returns
+    /// every file written by this recorder
+    pub fn finish(mut self) -> Result<Vec<PathBuf>, String> {
+        self.close_segment();
+        for handle in std::mem::take(&mut self.closing) {
+            handle.join().map_err(|_| "Audio finalizer panicked".to_string())??;
+        }
+        Ok(std::mem::take(&mut self.files))
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            // May run on the mixer thread when recording fails, so finalize in the background
+            thread::spawn(move || {
+                if let Err(e) = writer.finalize() {
+                    eprintln!("[ARCHIVE] Failed to finalize segment: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Session ids become directory names, so only UUID-like ids are accepted
+pub(crate) fn validate_session_id(session_id: &str) -> Result<(), String> {
+    if session_id.is_empty()
+        || !session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid session id: {}", session_id));
+    }
+    Ok(())
+}
+
+fn is_archived_audio(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "wav")
+}
+
+/// Called from the mixer with every tick; a failing recorder is dropped rather than stalling capture
+pub fn record(slot: &ArchiveSlot, samples: &[f32]) {
+    let Ok(mut guard) = slot.lock() else { return };
+    if let Some(recorder) = guard.as_mut() {
+        if let Err(e) = recorder.write(samples) {
+            eprintln!("[ARCHIVE] ✗ {} - recording stopped", e);
+            *guard = None;
+        }
+    }
+}
+
+// ============================================================================
+// RETENTION
+// ============================================================================
+
+struct ArchivedSession {
+    audio_dir: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// Deletes archived audio older than `retention_days`, then the oldest sessions
+/// until the archive fits in `max_archive_mb`. The active session is never pruned.
+fn enforce_retention(sessions_dir: &Path, config: &ArchiveConfig, active: Option<&str>) {
+    let Ok(entries) = fs::read_dir(sessions_dir) else { return };
+
+    let mut archived: Vec<ArchivedSession> = entries
+        .flatten()
+        .filter(|e| active.is_none_or(|id| e.file_name() != id))
+        .filter_map(|e| {
+            let audio_dir = e.path().join("audio");
+            let files: Vec<_> = fs::read_dir(&audio_dir).ok()?
+                .flatten()
+                .filter_map(|f| f.metadata().ok())
+                .filter(|m| m.is_file())
+                .collect();
+            let bytes = files.iter().map(|m| m.len()).sum();
+            let modified = files.iter()
+                .filter_map(|m| m.modified().ok())
+                .max()
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            Some(ArchivedSession { audio_dir, bytes, modified })
+        })
+        .collect();
+    archived.sort_by_key(|s| s.modified);
+
+    let max_age = Duration::from_secs(config.retention_days as u64 * 86_400);
+    let budget = config.max_archive_mb.saturating_mul(1024 * 1024);
+    let active_bytes = active
+        .map(|id| dir_size(&sessions_dir.join(id).join("audio")))
+        .unwrap_or(0);
+    let mut total: u64 = active_bytes + archived.iter().map(|s| s.bytes).sum::<u64>();
+
+    for session in archived {
+        let expired = config.retention_days > 0
+            && session.modified.elapsed().is_ok_and(|age| age > max_age);
+        let over_budget = config.max_archive_mb > 0 && total > budget;
+        if !expired && !over_budget {
+            continue;
+        }
+        match fs::remove_dir_all(&session.audio_dir) {
+            Ok(()) => {
+                total = total.saturating_sub(session.bytes);
+                println!("[ARCHIVE] Pruned {}", session.audio_dir.display());
+            }
+            Err(e) => eprintln!("[ARCHIVE] Failed to prune {}: {}", session.audio_dir.display(), e),
+        }
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().filter_map(|e| e.metadata().ok()).map(|m| m.len()).sum())
+        .unwrap_or(0)
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+/// Starts archiving the captured stream for `session_id`. Works whether or not
+/// capture is already running; audio is written from the next mixer tick.
+#[tauri::command]
+pub fn start_session_recording(
+    state: tauri::State<'_, AudioState>,
+    session_id: String,
+    config: Option<ArchiveConfig>,
+) -> Result<String, String> {
+    let config = config.unwrap_or_default();
+    let manager = SessionManager::new()?;
+    enforce_retention(manager.sessions_dir(), &config, Some(&session_id));
+
+    let recorder = SessionRecorder::new(&manager, &session_id, config)?;
+    let dir = recorder.dir().to_string_lossy().to_string();
+
+    // Finish the previous recorder outside the lock; the mixer writes under it
+    let previous = state.archive.lock().map_err(|e| e.to_string())?.replace(recorder);
+    if let Some(previous) = previous {
+        println!("[ARCHIVE] Replacing recorder for session {}", previous.session_id());
+        previous.finish()?;
+    }
+    println!("[ARCHIVE] Archiving session {} to {}", session_id, dir);
+    Ok(dir)
+}
+
+/// Stops archiving and returns the paths of the finalized audio segments
+#[tauri::command]
+pub fn stop_session_recording(state: tauri::State<'_, AudioState>) -> Result<Vec<String>, String> {
+    let recorder = state.archive.lock().map_err(|e| e.to_string())?.take();
+    match recorder {
+        Some(recorder) => Ok(recorder.finish()?
+            .into_iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect()),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[tauri::command]
+pub fn list_session_recordings(session_id: String) -> Result<Vec<String>, String> {
+    validate_session_id(&session_id)?;
+    let dir = SessionManager::new()?.audio_dir(&session_id);
+    let Ok(entries) = fs::read_dir(&dir) else { return Ok(Vec::new()) };
+    let mut files: Vec<String> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| is_archived_audio(p))
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("god-archive-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Tone bursts separated by digital silence, like speech with pauses
+    fn speech_like(secs: f32) -> Vec<f32> {
+        (0..(secs * TARGET_SAMPLE_RATE as f32) as usize)
+            .map(|i| {
+                let t = i as f32 / TARGET_SAMPLE_RATE as f32;
+                if (t * 2.0) as u32 % 3 == 2 { return 0.0; }
+                0.3 * (2.0 * std::f32::consts::PI * 180.0 * t).sin() + 0.1 * (2.0 * std::f32::consts::PI * 1300.0 * t).sin()
+            })
+            .collect()
+    }
+
+    fn data_size(path: &Path) -> u32 {
+        let bytes = fs::read(path).unwrap();
+        u32::from_le_bytes(bytes[40..44].try_into().unwrap())
+    }
+
+    #[test]
+    fn wav_header_is_refreshed_while_writing_and_exact_after_finalize() {
+        let dir = temp_dir();
+        let path = dir.join("test.wav");
+        let mut writer = WavStreamWriter::create(&path).unwrap();
+        for chunk in speech_like(6.0).chunks(160) {
+            writer.write(chunk).unwrap();
+        }
+        // Only the refresh at 5 s has reached the header; a crash now leaves 5 s playable
+        assert_eq!(data_size(&path), HEADER_REFRESH_SECS * TARGET_SAMPLE_RATE * 2);
+
+        let path = writer.finalize().unwrap();
+        assert_eq!(data_size(&path), 6 * TARGET_SAMPLE_RATE * 2);
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.duration(), 6 * TARGET_SAMPLE_RATE);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recorder_rolls_over_into_numbered_parts() {
+        let root = temp_dir();
+        let config = ArchiveConfig { segment_secs: 1, ..Default::default() };
+        let mut recorder = SessionRecorder::in_dir(root.join("session").join("audio"), "session", config).unwrap();
+        for chunk in speech_like(2.5).chunks(160) {
+            recorder.write(chunk).unwrap();
+        }
+        let files = recorder.finish().unwrap();
+        let names: Vec<String> = files.iter().map(|p| p.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, ["part-000.wav", "part-001.wav", "part-002.wav"]);
+
+        let lengths: Vec<u32> = files.iter().map(|p| hound::WavReader::open(p).unwrap().duration()).collect();
+        assert_eq!(lengths, [16000, 16000, 8000]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn retention_prunes_expired_then_oldest_but_never_the_active_session() {
+        let root = temp_dir();
+        let day = Duration::from_secs(86_400);
+        let archive = |id: &str, kb: usize, age_days: u32| {
+            let dir = root.join(id).join("audio");
+            fs::create_dir_all(&dir).unwrap();
+            let file = File::create(dir.join("part-000.wav")).unwrap();
+            file.set_len(kb as u64 * 1024).unwrap();
+            file.set_modified(SystemTime::now() - day * age_days).unwrap();
+        };
+        archive("expired", 1, 40);
+        archive("older", 800, 3);
+        archive("newer", 800, 2);
+        archive("active", 600, 90);
+
+        let config = ArchiveConfig { retention_days: 30, max_archive_mb: 2, ..Default::default() };
+        enforce_retention(&root, &config, Some("active"));
+
+        let kept = |id: &str| root.join(id).join("audio").exists();
+        assert!(!kept("expired"));
+        assert!(!kept("older")); // 2.2 MB total until this goes
+        assert!(kept("newer"));
+        assert!(kept("active"));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,24 +1,131 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use rubato::{Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use crossbeam_channel::{unbounded, Sender, Receiver};
+use std::time::{Duration, Instant};
+use crossbeam_channel::{bounded, unbounded, Sender, Receiver, RecvTimeoutError};
+use tauri::{AppHandle, Emitter};
+
+use crate::audio_archive::{self, ArchiveSlot};
+use crate::vad::{Vad, VadConfig, VadDecision, MAX_AGGRESSIVENESS};
 
 // Audio state for Tauri
 pub struct AudioState {
     pub is_recording: Mutex<bool>,
     pub stream_control: Mutex<Option<Sender<()>>>,
-    pub audio_tx: Mutex<Option<Sender<Vec<f32>>>>,
+    pub audio_tx: Mutex<Option<Sender<AudioChunk>>>,
     pub current_volume: Arc<Mutex<f32>>,
     pub capture_mode: Mutex<CaptureMode>,
+    pub mixer_config: Arc<Mutex<MixerConfig>>, // Read live by the running mixer
+    pub input_device_id: Mutex<Option<String>>,  // None = OS default
+    pub output_device_id: Mutex<Option<String>>, // Loopback source, None = OS default
+    pub archive: ArchiveSlot, // Opt-in session recorder, fed by the mixer
+    pub vad_config: Arc<Mutex<VadConfig>>,   // Read live by the running mixer
+    pub vad_state: Arc<Mutex<VadDecision>>,  // Latest mixer decision, polled by the UI
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum CaptureMode {
     MicOnly,
-    SystemOnly,  // WASAPI Loopback on Windows
+    SystemOnly,  // WASAPI loopback on Windows, Pulse/PipeWire monitor on Linux
     Both,
 }
 
+/// One mixer tick of 16 kHz mono audio
+#[derive(Clone, Debug)]
+pub struct AudioChunk {
+    pub samples: Vec<f32>,              // Mixed mic + system
+    pub sources: Option<SourceChannels>, // Set when MixerConfig::separate_channels is on
+    pub is_speech: bool,                 // VAD decision for this tick (includes hangover)
+}
+
+/// Per-source audio (gain applied), time-aligned with `AudioChunk::samples`
+#[derive(Clone, Debug)]
+pub struct SourceChannels {
+    pub mic: Vec<f32>,    // "Me"
+    pub system: Vec<f32>, // "Them"
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MixerConfig {
+    pub mic_gain: f32,
+    pub system_gain: f32,
+    pub separate_channels: bool,
+}
+
+impl Default for MixerConfig {
+    fn default() -> Self {
+        Self {
+            mic_gain: 1.0,
+            system_gain: 1.0,
+            separate_channels: false,
+        }
+    }
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Serialized to the frontend as `{ code, message }`
+#[derive(Debug, Clone)]
+pub enum CaptureError {
+    DeviceNotFound(String),
+    NoUsableConfig { device: String, tried: Vec<String> },
+    NoSystemAudio(String),
+    SetupTimeout,
+    Internal(String),
+}
+
+impl CaptureError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CaptureError::DeviceNotFound(_) => "DEVICE_NOT_FOUND",
+            CaptureError::NoUsableConfig { .. } => "NO_USABLE_CONFIG",
+            CaptureError::NoSystemAudio(_) => "NO_SYSTEM_AUDIO",
+            CaptureError::SetupTimeout => "SETUP_TIMEOUT",
+            CaptureError::Internal(_) => "INTERNAL",
+        }
+    }
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::DeviceNotFound(d) => write!(f, "Audio device not found: {}", d),
+            CaptureError::NoUsableConfig { device, tried } => {
+                write!(f, "No usable configuration for {}", device)?;
+                if !tried.is_empty() {
+                    write!(f, " (tried: {})", tried.join("; "))?;
+                }
+                Ok(())
+            }
+            CaptureError::NoSystemAudio(e) => write!(f, "System audio unavailable: {}", e),
+            CaptureError::SetupTimeout => write!(f, "Timed out opening audio devices"),
+            CaptureError::Internal(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Serialize for CaptureError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut st = serializer.serialize_struct("CaptureError", 2)?;
+        st.serialize_field("code", self.code())?;
+        st.serialize_field("message", &self.to_string())?;
+        st.end()
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for CaptureError {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        CaptureError::Internal(e.to_string())
+    }
+}
+
 impl Default for AudioState {
     fn default() -> Self {
         Self {
@@ -27,49 +134,182 @@ impl Default for AudioState {
             audio_tx: Mutex::new(None),
             current_volume: Arc::new(Mutex::new(0.0)),
             capture_mode: Mutex::new(CaptureMode::Both), // Default to Both (Mic + System)
+            mixer_config: Arc::new(Mutex::new(MixerConfig::default())),
+            input_device_id: Mutex::new(None),
+            output_device_id: Mutex::new(None),
+            archive: Arc::new(Mutex::new(None)),
+            vad_config: Arc::new(Mutex::new(VadConfig::default())),
+            vad_state: Arc::new(Mutex::new(VadDecision::default())),
         }
     }
 }
 
-const TARGET_SAMPLE_RATE: u32 = 16000;
-const MICRO_CHUNK_SAMPLES: usize = 160;
-const SILENCE_THRESHOLD: f32 = 0.01;
-const SILENCE_SKIP_CHUNKS: usize = 30;
+pub const TARGET_SAMPLE_RATE: u32 = 16000;
+const SILENCE_SKIP_CHUNKS: usize = 30;         // Non-speech ticks still sent after speech ends
 
-#[tauri::command]
-pub fn list_audio_devices() -> Result<Vec<String>, String> {
-    let mut names = Vec::new();
+// MIXER CONFIG
+const MIX_TICK_MS: u64 = 10;                   // Mixer output cadence (160 samples)
+const JITTER_MS: u64 = 60;                     // Output lags capture by this much
+const TARGET_BACKLOG: usize = 960;             // Expected queue depth (= JITTER_MS)
+const DRIFT_TOLERANCE: usize = 480;            // Backlog above target before trimming
+const MAX_BACKLOG: usize = 3200;               // 200ms - hard resync beyond this
+const MAX_GAIN: f32 = 4.0;
+
+// ============================================================================
+// DEVICE DISCOVERY
+// ============================================================================
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Input,
+    Output,
+}
+
+impl DeviceKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DeviceKind::Input => "input",
+            DeviceKind::Output => "output",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AudioDeviceInfo {
+    pub id: String,
+    pub name: String,
+    pub kind: DeviceKind,
+    pub host: String,
+    pub is_default: bool,
+    pub sample_rates: Vec<u32>,
+    pub channel_counts: Vec<u16>,
+}
+
+const COMMON_SAMPLE_RATES: &[u32] = &[8000, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 192000];
+
+/// Stable ID: cpal exposes no persistent device handle, so host + kind + name is used
+fn device_id(host: &cpal::Host, kind: DeviceKind, name: &str) -> String {
+    format!("{}:{}:{}", host.id().name(), kind.as_str(), name)
+}
+
+fn describe_device(host: &cpal::Host, device: &cpal::Device, kind: DeviceKind, default_name: &Option<String>) -> Option<AudioDeviceInfo> {
+    let name = device.name().ok()?;
     
-    // Default host devices
+    let ranges: Vec<cpal::SupportedStreamConfigRange> = match kind {
+        DeviceKind::Input => device.supported_input_configs().map(|c| c.collect()).unwrap_or_default(),
+        DeviceKind::Output => device.supported_output_configs().map(|c| c.collect()).unwrap_or_default(),
+    };
+    
+    let mut sample_rates = Vec::new();
+    let mut channel_counts = Vec::new();
+    for range in &ranges {
+        let (min, max) = (range.min_sample_rate().0, range.max_sample_rate().0);
+        sample_rates.push(min);
+        sample_rates.push(max);
+        sample_rates.extend(COMMON_SAMPLE_RATES.iter().filter(|r| (min..=max).contains(*r)));
+        channel_counts.push(range.channels());
+    }
+    sample_rates.sort_unstable();
+    sample_rates.dedup();
+    channel_counts.sort_unstable();
+    channel_counts.dedup();
+    
+    Some(AudioDeviceInfo {
+        id: device_id(host, kind, &name),
+        is_default: default_name.as_deref() == Some(name.as_str()),
+        name,
+        kind,
+        host: host.id().name().to_string(),
+        sample_rates,
+        channel_counts,
+    })
+}
+
+/// Resolve a device by ID, falling back to the OS default when no ID is selected
+fn find_device(host: &cpal::Host, kind: DeviceKind, id: Option<&str>) -> Option<cpal::Device> {
+    let Some(id) = id else {
+        return match kind {
+            DeviceKind::Input => host.default_input_device(),
+            DeviceKind::Output => host.default_output_device(),
+        };
+    };
+    
+    let mut devices = match kind {
+        DeviceKind::Input => host.input_devices().ok()?,
+        DeviceKind::Output => host.output_devices().ok()?,
+    };
+    devices.find(|d| d.name().map(|n| device_id(host, kind, &n) == id).unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
     let host = cpal::default_host();
-    names.push(format!("Host: {}", host.id().name()));
+    let mut devices = Vec::new();
     
-    names.push("--- Microphones ---".to_string());
-    if let Ok(devices) = host.input_devices() {
-        for device in devices {
-            if let Ok(name) = device.name() {
-                names.push(format!("  🎤 {}", name));
-            }
-        }
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+    if let Ok(inputs) = host.input_devices() {
+        devices.extend(inputs.filter_map(|d| describe_device(&host, &d, DeviceKind::Input, &default_input)));
     }
     
-    names.push("--- Output Devices ---".to_string());
-    if let Ok(devices) = host.output_devices() {
-        for device in devices {
-            if let Ok(name) = device.name() {
-                names.push(format!("  🔊 {}", name));
-            }
-        }
+    let default_output = host.default_output_device().and_then(|d| d.name().ok());
+    if let Ok(outputs) = host.output_devices() {
+        devices.extend(outputs.filter_map(|d| describe_device(&host, &d, DeviceKind::Output, &default_output)));
     }
     
-    // Check WASAPI availability (Windows only)
-    #[cfg(target_os = "windows")]
+    // Monitor sources are the loopback targets on Linux
+    #[cfg(all(target_os = "linux", feature = "pulse-monitor"))]
     {
-        names.push("--- WASAPI Loopback ---".to_string());
-        names.push("  ✓ Available for System Audio Capture".to_string());
+        let default_monitor = linux_monitor::default_source();
+        devices.extend(linux_monitor::list_sources().into_iter().map(|src| AudioDeviceInfo {
+            id: format!("{}{}", linux_monitor::ID_PREFIX, src.name),
+            is_default: default_monitor.as_deref() == Some(src.name.as_str()),
+            name: src.description,
+            kind: DeviceKind::Output,
+            host: "PulseAudio".to_string(),
+            sample_rates: Vec::new(),
+            channel_counts: Vec::new(),
+        }));
     }
     
-    Ok(names)
+    Ok(devices)
+}
+
+#[tauri::command]
+pub fn select_audio_device(
+    state: tauri::State<'_, AudioState>,
+    kind: String,
+    device_id: Option<String>,
+) -> Result<String, String> {
+    let kind = match kind.as_str() {
+        "input" => DeviceKind::Input,
+        "output" => DeviceKind::Output,
+        _ => return Err("Invalid device kind".to_string()),
+    };
+    
+    if let Some(ref id) = device_id {
+        let host = cpal::default_host();
+        #[cfg(all(target_os = "linux", feature = "pulse-monitor"))]
+        let is_monitor = kind == DeviceKind::Output
+            && linux_monitor::source_from_id(id)
+                .map(|src| linux_monitor::list_sources().iter().any(|s| s.name == src))
+                .unwrap_or(false);
+        #[cfg(not(all(target_os = "linux", feature = "pulse-monitor")))]
+        let is_monitor = false;
+        
+        if !is_monitor && find_device(&host, kind, Some(id)).is_none() {
+            return Err(format!("Device not found: {}", id));
+        }
+    }
+    
+    let slot = match kind {
+        DeviceKind::Input => &state.input_device_id,
+        DeviceKind::Output => &state.output_device_id,
+    };
+    *slot.lock().map_err(|e| e.to_string())? = device_id.clone();
+    
+    println!("[AUDIO] Selected {} device: {}", kind.as_str(), device_id.as_deref().unwrap_or("default"));
+    Ok(format!("Selected: {}", device_id.unwrap_or_else(|| "default".to_string())))
 }
 
 #[tauri::command]
@@ -86,6 +326,44 @@ pub fn set_capture_mode(state: tauri::State<'_, AudioState>, mode: String) -> Re
     Ok(format!("Mode: {:?}", new_mode))
 }
 
+#[tauri::command]
+pub fn set_mixer_config(
+    state: tauri::State<'_, AudioState>,
+    mic_gain: Option<f32>,
+    system_gain: Option<f32>,
+    separate_channels: Option<bool>,
+) -> Result<MixerConfig, String> {
+    let mut config = state.mixer_config.lock().map_err(|e| e.to_string())?;
+    
+    for gain in [mic_gain, system_gain].into_iter().flatten() {
+        if !(0.0..=MAX_GAIN).contains(&gain) {
+            return Err(format!("Gain out of range (0-{}): {}", MAX_GAIN, gain));
+        }
+    }
+    if let Some(g) = mic_gain { config.mic_gain = g; }
+    if let Some(g) = system_gain { config.system_gain = g; }
+    if let Some(sep) = separate_channels { config.separate_channels = sep; }
+    
+    println!("[AUDIO] Mixer: {:?}", *config);
+    Ok(*config)
+}
+
+#[tauri::command]
+pub fn set_vad_config(state: tauri::State<'_, AudioState>, config: VadConfig) -> Result<VadConfig, String> {
+    if config.aggressiveness > MAX_AGGRESSIVENESS {
+        return Err(format!("VAD aggressiveness out of range (0-{}): {}", MAX_AGGRESSIVENESS, config.aggressiveness));
+    }
+    *state.vad_config.lock().map_err(|e| e.to_string())? = config;
+    println!("[AUDIO] VAD: {:?}", config);
+    Ok(config)
+}
+
+#[tauri::command]
+pub fn get_vad_state(state: tauri::State<'_, AudioState>) -> Result<VadDecision, String> {
+    let decision = state.vad_state.lock().map_err(|e| e.to_string())?;
+    Ok(*decision)
+}
+
 #[tauri::command]
 pub fn get_current_volume(state: tauri::State<'_, AudioState>) -> Result<f32, String> {
     let volume = state.current_volume.lock().map_err(|e| e.to_string())?;
@@ -103,242 +381,745 @@ fn to_mono(data: &[f32], channels: u16) -> Vec<f32> {
         .collect()
 }
 
-fn decimate(samples: Vec<f32>, from_rate: u32, to_rate: u32) -> Vec<f32> {
-    if from_rate == to_rate { return samples; }
-    let factor = from_rate / to_rate;
-    if factor == 0 { return samples; }
-    samples.into_iter().step_by(factor as usize).collect()
+// ============================================================================
+// RESAMPLING
+// ============================================================================
+
+const RESAMPLER_CHUNK: usize = 1024;
+
+/// Streaming band-limited (windowed sinc) resampler for one mono stream.
+/// Accepts arbitrarily sized input blocks and compensates the filter delay,
+/// so `process` + `flush` yield exactly `len * to / from` samples.
+pub struct StreamResampler {
+    inner: Option<SincFixedIn<f32>>, // None when rates already match
+    ratio: f64,
+    pending: Vec<f32>,
+    skip: usize,
+    consumed: u64,
+    produced: u64,
+}
+
+impl StreamResampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Result<Self, String> {
+        if from_rate == 0 || to_rate == 0 {
+            return Err(format!("Invalid sample rate: {} -> {}", from_rate, to_rate));
+        }
+        let ratio = to_rate as f64 / from_rate as f64;
+        
+        let inner = if from_rate == to_rate {
+            None
+        } else {
+            let params = SincInterpolationParameters {
+                sinc_len: 128,
+                f_cutoff: 0.95,
+                oversampling_factor: 128,
+                interpolation: SincInterpolationType::Linear,
+                window: WindowFunction::BlackmanHarris2,
+            };
+            Some(SincFixedIn::<f32>::new(ratio, 1.0, params, RESAMPLER_CHUNK, 1)
+                .map_err(|e| format!("Resampler: {}", e))?)
+        };
+        let skip = inner.as_ref().map(|r| r.output_delay()).unwrap_or(0);
+        
+        Ok(Self { inner, ratio, pending: Vec::new(), skip, consumed: 0, produced: 0 })
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let Some(ref mut resampler) = self.inner else { return input.to_vec(); };
+        
+        self.pending.extend_from_slice(input);
+        self.consumed += input.len() as u64;
+        
+        let mut out = Vec::new();
+        while self.pending.len() >= resampler.input_frames_next() {
+            let n = resampler.input_frames_next();
+            if let Ok(mut wave) = resampler.process(&[&self.pending[..n]], None) {
+                out.append(&mut wave[0]);
+            }
+            self.pending.drain(..n);
+        }
+        self.compensate(out)
+    }
+
+    /// Push out buffered input and the filter tail (end of stream)
+    pub fn flush(&mut self) -> Vec<f32> {
+        let Some(ref mut resampler) = self.inner else { return Vec::new(); };
+        
+        let expected = (self.consumed as f64 * self.ratio).round() as u64;
+        let mut out = Vec::new();
+        let remaining = std::mem::take(&mut self.pending);
+        if let Ok(mut wave) = resampler.process_partial(Some(&[&remaining[..]]), None) {
+            out.append(&mut wave[0]);
+        }
+        // Zero-fed calls drain the delay line; bounded in case the resampler stalls
+        let mut total = self.produced + out.len().saturating_sub(self.skip) as u64;
+        for _ in 0..8 {
+            if total >= expected { break; }
+            match resampler.process_partial::<&[f32]>(None, None) {
+                Ok(mut wave) => {
+                    total += wave[0].len() as u64;
+                    out.append(&mut wave[0]);
+                }
+                Err(_) => break,
+            }
+        }
+        
+        let mut out = self.compensate(out);
+        let overshoot = (self.produced.saturating_sub(expected)) as usize;
+        out.truncate(out.len().saturating_sub(overshoot));
+        self.produced = self.produced.min(expected);
+        out
+    }
+
+    fn compensate(&mut self, mut out: Vec<f32>) -> Vec<f32> {
+        let skip = self.skip.min(out.len());
+        out.drain(..skip);
+        self.skip -= skip;
+        self.produced += out.len() as u64;
+        out
+    }
+}
+
+// ============================================================================
+// STREAM CONSTRUCTION
+// ============================================================================
+
+/// Per-source jitter queue, filled at 16 kHz mono by a stream callback
+type SourceQueue = Arc<Mutex<VecDeque<f32>>>;
+
+// Tried in order when the device's default config can't be opened
+const FORMAT_PREFERENCE: &[SampleFormat] = &[
+    SampleFormat::F32, SampleFormat::I16, SampleFormat::I32, SampleFormat::U16,
+    SampleFormat::F64, SampleFormat::I8, SampleFormat::U8, SampleFormat::U32,
+];
+const PREFERRED_RATES: &[u32] = &[48000, 16000, 44100];
+
+fn build_capture_stream(
+    device: &cpal::Device,
+    config: &cpal::SupportedStreamConfig,
+    queue: SourceQueue,
+    label: &'static str,
+) -> Result<cpal::Stream, String> {
+    let stream_config: cpal::StreamConfig = config.config();
+    match config.sample_format() {
+        SampleFormat::F32 => build_typed_stream::<f32>(device, &stream_config, queue, label),
+        SampleFormat::F64 => build_typed_stream::<f64>(device, &stream_config, queue, label),
+        SampleFormat::I8 => build_typed_stream::<i8>(device, &stream_config, queue, label),
+        SampleFormat::I16 => build_typed_stream::<i16>(device, &stream_config, queue, label),
+        SampleFormat::I32 => build_typed_stream::<i32>(device, &stream_config, queue, label),
+        SampleFormat::U8 => build_typed_stream::<u8>(device, &stream_config, queue, label),
+        SampleFormat::U16 => build_typed_stream::<u16>(device, &stream_config, queue, label),
+        SampleFormat::U32 => build_typed_stream::<u32>(device, &stream_config, queue, label),
+        other => Err(format!("Unsupported sample format {:?}", other)),
+    }
+}
+
+fn build_typed_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: SourceQueue,
+    label: &'static str,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels;
+    let mut resampler = StreamResampler::new(config.sample_rate.0, TARGET_SAMPLE_RATE)?;
+    
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            if data.is_empty() { return; }
+            
+            let samples: Vec<f32> = data.iter().map(|s| s.to_sample::<f32>()).collect();
+            let mono = to_mono(&samples, channels);
+            let resampled = resampler.process(&mono);
+            
+            if let Ok(mut q) = queue.lock() {
+                q.extend(resampled);
+            }
+        },
+        move |e| eprintln!("[AUDIO] {} error: {}", label, e),
+        None
+    ).map_err(|e| e.to_string())
+}
+
+/// Default config first, then every supported format in preference order
+fn candidate_configs(device: &cpal::Device, kind: DeviceKind) -> Vec<cpal::SupportedStreamConfig> {
+    let (default, ranges): (_, Vec<cpal::SupportedStreamConfigRange>) = match kind {
+        DeviceKind::Input => (
+            device.default_input_config().ok(),
+            device.supported_input_configs().map(|c| c.collect()).unwrap_or_default(),
+        ),
+        DeviceKind::Output => (
+            device.default_output_config().ok(),
+            device.supported_output_configs().map(|c| c.collect()).unwrap_or_default(),
+        ),
+    };
+    
+    let mut candidates: Vec<cpal::SupportedStreamConfig> = default.into_iter().collect();
+    for format in FORMAT_PREFERENCE {
+        for range in ranges.iter().filter(|r| r.sample_format() == *format) {
+            let rate = PREFERRED_RATES.iter()
+                .copied()
+                .find(|r| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(r))
+                .unwrap_or(range.max_sample_rate().0);
+            let config = (*range).with_sample_rate(cpal::SampleRate(rate));
+            if !candidates.contains(&config) {
+                candidates.push(config);
+            }
+        }
+    }
+    candidates
+}
+
+/// Negotiate a working config for a device and open a capture stream on it
+fn open_stream(
+    device: &cpal::Device,
+    kind: DeviceKind,
+    queue: SourceQueue,
+    label: &'static str,
+) -> Result<cpal::Stream, CaptureError> {
+    let name = device.name().unwrap_or_default();
+    let mut tried = Vec::new();
+    
+    for config in candidate_configs(device, kind) {
+        let desc = format!("{:?} {}Hz {}ch", config.sample_format(), config.sample_rate().0, config.channels());
+        match build_capture_stream(device, &config, queue.clone(), label) {
+            Ok(stream) => {
+                println!("[AUDIO] {} format: {}", label, desc);
+                return Ok(stream);
+            }
+            Err(e) => {
+                eprintln!("[AUDIO] {} rejected {}: {}", label, desc, e);
+                tried.push(format!("{} ({})", desc, e));
+            }
+        }
+    }
+    
+    Err(CaptureError::NoUsableConfig { device: name, tried })
+}
+
+// ============================================================================
+// MIXER
+// ============================================================================
+
+/// Pull exactly `n` samples from a source, keeping its backlog near the target.
+/// Underruns (stalled device, or WASAPI loopback going quiet when nothing plays)
+/// are padded with silence; a device whose clock runs fast slowly accumulates
+/// backlog, which is shaved one sample per tick, or resynced if it runs away.
+fn take_aligned(queue: &SourceQueue, n: usize) -> Vec<f32> {
+    let Ok(mut q) = queue.lock() else { return vec![0.0; n]; };
+    
+    let available = q.len().min(n);
+    let mut out: Vec<f32> = q.drain(..available).collect();
+    out.resize(n, 0.0);
+    
+    if q.len() > MAX_BACKLOG {
+        let excess = q.len() - TARGET_BACKLOG;
+        q.drain(..excess);
+    } else if q.len() > TARGET_BACKLOG + DRIFT_TOLERANCE {
+        q.pop_front();
+    }
+    out
+}
+
+/// Live state the mixer shares with `AudioState`
+struct MixerHandles {
+    config: Arc<Mutex<MixerConfig>>,
+    volume: Arc<Mutex<f32>>,
+    vad_config: Arc<Mutex<VadConfig>>,
+    vad_state: Arc<Mutex<VadDecision>>,
+    archive: ArchiveSlot,
+}
+
+/// Drive output from the wall clock so both device clocks are resolved against one timeline
+fn run_mixer(
+    mic: Option<SourceQueue>,
+    system: Option<SourceQueue>,
+    handles: MixerHandles,
+    tx: Option<Sender<AudioChunk>>,
+    stop_rx: Receiver<()>,
+) {
+    let MixerHandles { config, volume, vad_config, vad_state, archive } = handles;
+    let start = Instant::now() + Duration::from_millis(JITTER_MS);
+    let mut emitted: u64 = 0;
+    let mut silence_count: usize = 0;
+    let mut active_vad = vad_config.lock().map(|c| *c).unwrap_or_default();
+    let mut vad = Vad::new(active_vad);
+    
+    while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(Duration::from_millis(MIX_TICK_MS)) {
+        let now = Instant::now();
+        if now < start { continue; }
+        let due = ((now - start).as_secs_f64() * TARGET_SAMPLE_RATE as f64) as u64;
+        let n = due.saturating_sub(emitted) as usize;
+        if n == 0 { continue; }
+        emitted += n as u64;
+        
+        let cfg = config.lock().map(|c| *c).unwrap_or_default();
+        let mic_frame: Vec<f32> = mic.as_ref()
+            .map(|q| take_aligned(q, n).into_iter().map(|s| s * cfg.mic_gain).collect())
+            .unwrap_or_else(|| vec![0.0; n]);
+        let system_frame: Vec<f32> = system.as_ref()
+            .map(|q| take_aligned(q, n).into_iter().map(|s| s * cfg.system_gain).collect())
+            .unwrap_or_else(|| vec![0.0; n]);
+        
+        let mixed: Vec<f32> = mic_frame.iter().zip(&system_frame)
+            .map(|(a, b)| (a + b).clamp(-1.0, 1.0))
+            .collect();
+        
+        let rms = calculate_rms(&mixed);
+        if let Ok(mut v) = volume.lock() { *v = rms; }
+        
+        // Archive before the silence gate so recordings keep real time
+        audio_archive::record(&archive, &mixed);
+        
+        // Speech gating
+        let vad_cfg = vad_config.lock().map(|c| *c).unwrap_or_default();
+        if vad_cfg != active_vad {
+            vad.set_config(vad_cfg);
+            active_vad = vad_cfg;
+        }
+        let decision = vad.process(&mixed);
+        if let Ok(mut v) = vad_state.lock() { *v = decision; }
+        
+        if decision.is_speech {
+            silence_count = 0;
+        } else {
+            silence_count += 1;
+            if silence_count > SILENCE_SKIP_CHUNKS { continue; }
+        }
+        
+        let chunk = AudioChunk {
+            samples: mixed,
+            sources: cfg.separate_channels.then_some(SourceChannels {
+                mic: mic_frame,
+                system: system_frame,
+            }),
+            is_speech: decision.is_speech,
+        };
+        if let Some(ref tx) = tx {
+            let _ = tx.send(chunk);
+        }
+    }
+    if let Ok(mut v) = vad_state.lock() { *v = VadDecision::default(); };
+}
+
+// ============================================================================
+// SYSTEM AUDIO - WASAPI LOOPBACK (Windows)
+// ============================================================================
+
+/// Keeps system audio flowing into its source queue until dropped
+enum SystemStream {
+    Cpal(cpal::Stream),
+    #[cfg(all(target_os = "linux", feature = "pulse-monitor"))]
+    Pulse(linux_monitor::MonitorCapture), // Runs from the moment it is opened
+}
+
+impl SystemStream {
+    fn play(&self) -> Result<(), String> {
+        match self {
+            SystemStream::Cpal(stream) => stream.play().map_err(|e| e.to_string()),
+            #[cfg(all(target_os = "linux", feature = "pulse-monitor"))]
+            SystemStream::Pulse(capture) if capture.is_running() => Ok(()),
+            #[cfg(all(target_os = "linux", feature = "pulse-monitor"))]
+            SystemStream::Pulse(_) => Err("Monitor stream stopped".to_string()),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn open_system_stream(output_id: Option<&str>, queue: &SourceQueue) -> Result<SystemStream, String> {
+    use cpal::available_hosts;
+    
+    println!("[AUDIO] Attempting system audio capture...");
+    
+    // Try WASAPI host first
+    let wasapi_host = available_hosts()
+        .into_iter()
+        .find(|h| h.name().contains("WASAPI"))
+        .and_then(|id| cpal::host_from_id(id).ok());
+    
+    let host = wasapi_host.unwrap_or_else(|| {
+        println!("[AUDIO] WASAPI not available, using default host");
+        cpal::default_host()
+    });
+    
+    // Strategy 1: Try loopback from the selected (or default) output device
+    let loopback_result = find_device(&host, DeviceKind::Output, output_id).and_then(|device| {
+        let name = device.name().unwrap_or_default();
+        println!("[AUDIO] Trying loopback on output device: {}", name);
+        
+        open_stream(&device, DeviceKind::Output, queue.clone(), "Loopback stream").ok()
+    });
+    
+    if let Some(stream) = loopback_result {
+        println!("[AUDIO] ✓ WASAPI loopback stream created successfully");
+        return Ok(SystemStream::Cpal(stream));
+    }
+    
+    // Strategy 2: Try finding Stereo Mix or similar virtual device
+    println!("[AUDIO] Loopback failed, searching for Stereo Mix...");
+    
+    let stereo_mix = host.input_devices().ok().and_then(|mut devices| {
+        devices.find(|d| {
+            if let Ok(name) = d.name() {
+                let name_lower = name.to_lowercase();
+                name_lower.contains("stereo mix") ||
+                name_lower.contains("what u hear") ||
+                name_lower.contains("wave out") ||
+                name_lower.contains("loopback")
+            } else {
+                false
+            }
+        })
+    });
+    
+    if let Some(device) = stereo_mix {
+        let name = device.name().unwrap_or_default();
+        println!("[AUDIO] Found virtual capture device: {}", name);
+        
+        if let Ok(stream) = open_stream(&device, DeviceKind::Input, queue.clone(), "Stereo Mix") {
+            return Ok(SystemStream::Cpal(stream));
+        }
+    }
+    
+    eprintln!("[AUDIO] ✗ System audio capture not available");
+    eprintln!("[AUDIO] To enable system audio capture:");
+    eprintln!("  1. Right-click Sound icon in system tray → Sounds");
+    eprintln!("  2. Go to Recording tab");
+    eprintln!("  3. Right-click → Show Disabled Devices");
+    eprintln!("  4. Enable 'Stereo Mix' if available");
+    Err("No loopback device or Stereo Mix available".to_string())
+}
+
+// ============================================================================
+// SYSTEM AUDIO - PULSEAUDIO / PIPEWIRE MONITOR SOURCES (Linux)
+// ============================================================================
+
+#[cfg(all(target_os = "linux", feature = "pulse-monitor"))]
+mod linux_monitor {
+    use std::ffi::{c_char, c_int, c_void, CStr, CString};
+    use std::process::Command;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, OnceLock};
+    use std::thread;
+    use crossbeam_channel::bounded;
+    use libloading::Library;
+    
+    use super::{SourceQueue, TARGET_SAMPLE_RATE};
+    
+    pub const ID_PREFIX: &str = "pulse:monitor:";
+    
+    #[derive(Clone, Debug)]
+    pub struct MonitorSource {
+        pub name: String,        // e.g. alsa_output.pci-0000_00_1f.3.analog-stereo.monitor
+        pub description: String, // e.g. Monitor of Built-in Audio Analog Stereo
+    }
+    
+    fn pactl(args: &[&str]) -> Option<String> {
+        let output = Command::new("pactl").args(args).output().ok()?;
+        if !output.status.success() { return None; }
+        String::from_utf8(output.stdout).ok()
+    }
+    
+    /// Monitor sources from the PulseAudio server (pipewire-pulse answers the same protocol)
+    pub fn list_sources() -> Vec<MonitorSource> {
+        let Some(listing) = pactl(&["list", "sources"]) else { return Vec::new(); };
+        
+        let mut sources = Vec::new();
+        let mut name: Option<String> = None;
+        for line in listing.lines().map(str::trim) {
+            if let Some(n) = line.strip_prefix("Name: ") {
+                name = Some(n.to_string());
+            } else if let Some(d) = line.strip_prefix("Description: ") {
+                if let Some(n) = name.take() {
+                    if n.ends_with(".monitor") {
+                        sources.push(MonitorSource { name: n, description: d.to_string() });
+                    }
+                }
+            }
+        }
+        sources
+    }
+    
+    /// Monitor of the current default sink, i.e. whatever the meeting app is playing to
+    pub fn default_source() -> Option<String> {
+        let sources = list_sources();
+        pactl(&["get-default-sink"])
+            .map(|s| format!("{}.monitor", s.trim()))
+            .filter(|m| sources.iter().any(|s| &s.name == m))
+            .or_else(|| sources.into_iter().next().map(|s| s.name))
+    }
+    
+    pub fn source_from_id(id: &str) -> Option<&str> {
+        id.strip_prefix(ID_PREFIX)
+    }
+    
+    // --- Minimal libpulse-simple client: record one named source ---
+    
+    #[repr(C)]
+    struct PaSimple { _private: [u8; 0] }
+    
+    #[repr(C)]
+    struct PaSampleSpec { format: c_int, rate: u32, channels: u8 }
+    
+    #[repr(C)]
+    struct PaBufferAttr { maxlength: u32, tlength: u32, prebuf: u32, minreq: u32, fragsize: u32 }
+    
+    const PA_SAMPLE_FLOAT32LE: c_int = 5;
+    const PA_STREAM_RECORD: c_int = 2;
+    const READ_FRAMES: usize = 160; // 10 ms at 16 kHz, so a stop request is seen promptly
+    const LIBRARY: &str = "libpulse-simple.so.0";
+    
+    type NewFn = unsafe extern "C" fn(
+        server: *const c_char, name: *const c_char, dir: c_int, dev: *const c_char,
+        stream_name: *const c_char, ss: *const PaSampleSpec, map: *const c_void,
+        attr: *const PaBufferAttr, error: *mut c_int,
+    ) -> *mut PaSimple;
+    type ReadFn = unsafe extern "C" fn(s: *mut PaSimple, data: *mut c_void, bytes: usize, error: *mut c_int) -> c_int;
+    type FreeFn = unsafe extern "C" fn(s: *mut PaSimple);
+    type StrerrorFn = unsafe extern "C" fn(error: c_int) -> *const c_char;
+    
+    /// libpulse-simple, loaded on first use so machines without it still build and
+    /// run; only monitor capture fails
+    struct PulseSimple {
+        new: NewFn,
+        read: ReadFn,
+        free: FreeFn,
+        strerror: StrerrorFn,
+        _library: Library, // Keeps the function pointers above valid
+    }
+    
+    impl PulseSimple {
+        fn load() -> Result<Self, String> {
+            let missing = |e: libloading::Error| format!("{} not available: {}", LIBRARY, e);
+            // SAFETY: the library has no initialisers with preconditions, and each symbol
+            // is cast to the signature declared in <pulse/simple.h> / <pulse/error.h>
+            unsafe {
+                let library = Library::new(LIBRARY).map_err(missing)?;
+                Ok(Self {
+                    new: *library.get::<NewFn>(b"pa_simple_new\0").map_err(missing)?,
+                    read: *library.get::<ReadFn>(b"pa_simple_read\0").map_err(missing)?,
+                    free: *library.get::<FreeFn>(b"pa_simple_free\0").map_err(missing)?,
+                    strerror: *library.get::<StrerrorFn>(b"pa_strerror\0").map_err(missing)?,
+                    _library: library,
+                })
+            }
+        }
+        
+        fn get() -> Result<&'static Self, String> {
+            static PULSE: OnceLock<Result<PulseSimple, String>> = OnceLock::new();
+            PULSE.get_or_init(Self::load).as_ref().map_err(Clone::clone)
+        }
+        
+        fn error(&self, code: c_int) -> String {
+            // SAFETY: pa_strerror returns a static NUL-terminated string for any code
+            unsafe { CStr::from_ptr((self.strerror)(code)) }.to_string_lossy().into_owned()
+        }
+    }
+    
+    /// Records a monitor source by name on its own thread. The server converts
+    /// to 16 kHz mono float, so samples go straight into the queue.
+    pub struct MonitorCapture {
+        stop: Arc<AtomicBool>,
+        thread: Option<thread::JoinHandle<()>>,
+    }
+    
+    impl MonitorCapture {
+        pub fn open(source: &str, queue: SourceQueue) -> Result<Self, String> {
+            let pulse = PulseSimple::get()?;
+            let device = CString::new(source).map_err(|e| e.to_string())?;
+            let stop = Arc::new(AtomicBool::new(false));
+            let (ready_tx, ready_rx) = bounded::<Result<(), String>>(1);
+            
+            let thread = thread::spawn({
+                let stop = stop.clone();
+                move || {
+                    let spec = PaSampleSpec { format: PA_SAMPLE_FLOAT32LE, rate: TARGET_SAMPLE_RATE, channels: 1 };
+                    let bytes = (READ_FRAMES * std::mem::size_of::<f32>()) as u32;
+                    let attr = PaBufferAttr { maxlength: u32::MAX, tlength: u32::MAX, prebuf: u32::MAX, minreq: u32::MAX, fragsize: bytes };
+                    let mut error: c_int = 0;
+                    // SAFETY: every pointer is valid for the call; the connection is only used on this thread
+                    let handle = unsafe {
+                        (pulse.new)(
+                            std::ptr::null(), c"GOD-V8".as_ptr(), PA_STREAM_RECORD, device.as_ptr(),
+                            c"System audio".as_ptr(), &spec, std::ptr::null(), &attr, &mut error,
+                        )
+                    };
+                    if handle.is_null() {
+                        let _ = ready_tx.send(Err(pulse.error(error)));
+                        return;
+                    }
+                    let _ = ready_tx.send(Ok(()));
+                    
+                    let mut buffer = vec![0.0f32; READ_FRAMES];
+                    while !stop.load(Ordering::Relaxed) {
+                        // SAFETY: buffer holds exactly `bytes` bytes and handle is live until freed below
+                        let read = unsafe { (pulse.read)(handle, buffer.as_mut_ptr().cast(), bytes as usize, &mut error) };
+                        if read < 0 {
+                            eprintln!("[AUDIO] Monitor stream error: {}", pulse.error(error));
+                            break;
+                        }
+                        if let Ok(mut q) = queue.lock() {
+                            q.extend(&buffer);
+                        }
+                    }
+                    // SAFETY: handle came from pa_simple_new and is not used after this
+                    unsafe { (pulse.free)(handle) };
+                }
+            });
+            
+            match ready_rx.recv() {
+                Ok(Ok(())) => Ok(Self { stop, thread: Some(thread) }),
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err("Monitor capture thread exited".to_string()),
+            }
+        }
+        
+        pub fn is_running(&self) -> bool {
+            self.thread.as_ref().is_some_and(|t| !t.is_finished())
+        }
+    }
+    
+    impl Drop for MonitorCapture {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_system_stream(output_id: Option<&str>, queue: &SourceQueue) -> Result<SystemStream, String> {
+    println!("[AUDIO] Attempting system audio capture...");
+    let host = cpal::default_host();
+    
+    // Strategy 1: ALSA configs that expose monitor sources as their own PCM
+    let alsa_monitor = host.input_devices().ok().and_then(|mut devices| {
+        devices.find(|d| d.name().map(|n| n.to_lowercase().contains("monitor")).unwrap_or(false))
+    });
+    if output_id.is_none() {
+        if let Some(device) = alsa_monitor {
+            let name = device.name().unwrap_or_default();
+            println!("[AUDIO] Found ALSA monitor device: {}", name);
+            if let Ok(stream) = open_stream(&device, DeviceKind::Input, queue.clone(), "Monitor stream") {
+                return Ok(SystemStream::Cpal(stream));
+            }
+        }
+    }
+    
+    // Strategy 2: Record the monitor source by name from the PulseAudio server
+    #[cfg(feature = "pulse-monitor")]
+    {
+        let source = output_id
+            .and_then(linux_monitor::source_from_id)
+            .map(str::to_string)
+            .or_else(linux_monitor::default_source)
+            .ok_or("No PulseAudio/PipeWire monitor source found (is pactl installed?)")?;
+        
+        println!("[AUDIO] Capturing monitor source: {}", source);
+        linux_monitor::MonitorCapture::open(&source, queue.clone())
+            .map(SystemStream::Pulse)
+            .map_err(|e| format!("Failed to open monitor source {}: {}", source, e))
+    }
+    #[cfg(not(feature = "pulse-monitor"))]
+    Err("No ALSA monitor device found, and this build has no PulseAudio monitor capture (pulse-monitor feature)".to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn open_system_stream(_output_id: Option<&str>, _queue: &SourceQueue) -> Result<SystemStream, String> {
+    Err("System audio capture is not supported on this platform".to_string())
 }
 
 #[tauri::command]
-pub fn start_audio_capture(state: tauri::State<'_, AudioState>) -> Result<String, String> {
-    let mut is_rec = state.is_recording.lock().map_err(|e| e.to_string())?;
+pub fn start_audio_capture(state: tauri::State<'_, AudioState>, app: AppHandle) -> Result<String, CaptureError> {
+    let mut is_rec = state.is_recording.lock()?;
     if *is_rec {
         return Ok("Already recording".to_string());
     }
 
     let (stop_tx, stop_rx) = unbounded::<()>();
-    {
-        let mut control = state.stream_control.lock().map_err(|e| e.to_string())?;
-        *control = Some(stop_tx);
-    }
+    let (ready_tx, ready_rx) = bounded::<Result<String, CaptureError>>(1);
     
-    let audio_tx = state.audio_tx.lock().map_err(|e| e.to_string())?.clone();
-    let capture_mode = *state.capture_mode.lock().map_err(|e| e.to_string())?;
-    let volume = state.current_volume.clone();
+    let audio_tx = state.audio_tx.lock()?.clone();
+    let capture_mode = *state.capture_mode.lock()?;
+    let input_id = state.input_device_id.lock()?.clone();
+    let output_id = state.output_device_id.lock()?.clone();
+    let handles = MixerHandles {
+        config: state.mixer_config.clone(),
+        volume: state.current_volume.clone(),
+        vad_config: state.vad_config.clone(),
+        vad_state: state.vad_state.clone(),
+        archive: state.archive.clone(),
+    };
 
     println!("[AUDIO] Starting capture. Mode: {:?}", capture_mode);
 
+    // Streams are created and owned by this thread (cpal::Stream is not Send)
     thread::spawn(move || {
-        let buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
-        let silence_count: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
-        
         // === MICROPHONE CAPTURE ===
-        let mic_stream = if capture_mode == CaptureMode::MicOnly || capture_mode == CaptureMode::Both {
+        let mic_queue: SourceQueue = Arc::new(Mutex::new(VecDeque::new()));
+        let mic_result = if capture_mode == CaptureMode::MicOnly || capture_mode == CaptureMode::Both {
             let host = cpal::default_host();
-            if let Some(device) = host.default_input_device() {
-                let name = device.name().unwrap_or_default();
-                println!("[AUDIO] Mic: {}", name);
-                
-                if let Ok(config) = device.default_input_config() {
-                    let channels = config.channels();
-                    let sample_rate = config.sample_rate().0;
-                    
-                    let tx = audio_tx.clone();
-                    let buf = buffer.clone();
-                    let sil = silence_count.clone();
-                    let vol = volume.clone();
-                    
-                    let stream = device.build_input_stream(
-                        &config.into(),
-                        move |data: &[f32], _| {
-                            if data.is_empty() { return; }
-                            
-                            let mono = to_mono(data, channels);
-                            let resampled = decimate(mono, sample_rate, TARGET_SAMPLE_RATE);
-                            
-                            let rms = calculate_rms(&resampled);
-                            if let Ok(mut v) = vol.lock() { *v = rms; }
-                            
-                            // Silence detection
-                            if let Ok(mut count) = sil.lock() {
-                                if rms < SILENCE_THRESHOLD {
-                                    *count += 1;
-                                    if *count > SILENCE_SKIP_CHUNKS { return; }
-                                } else {
-                                    *count = 0;
-                                }
-                            }
-                            
-                            // Buffer and send chunks
-                            if let Ok(mut b) = buf.lock() {
-                                b.extend(resampled);
-                                while b.len() >= MICRO_CHUNK_SAMPLES {
-                                    let chunk: Vec<f32> = b.drain(..MICRO_CHUNK_SAMPLES).collect();
-                                    if let Some(ref tx) = tx {
-                                        let _ = tx.send(chunk);
-                                    }
-                                }
-                            }
-                        },
-                        |e| eprintln!("[AUDIO] Mic error: {}", e),
-                        None
-                    ).ok();
-                    stream
-                } else { None }
-            } else { None }
+            let opened = match find_device(&host, DeviceKind::Input, input_id.as_deref()) {
+                Some(device) => {
+                    println!("[AUDIO] Mic: {}", device.name().unwrap_or_default());
+                    open_stream(&device, DeviceKind::Input, mic_queue.clone(), "Mic")
+                }
+                None => Err(CaptureError::DeviceNotFound(
+                    input_id.clone().unwrap_or_else(|| "default input".to_string()),
+                )),
+            };
+            Some(opened)
         } else { None };
         
-        // === SYSTEM AUDIO (WASAPI LOOPBACK) - Windows Only ===
-        #[cfg(target_os = "windows")]
-        let loopback_stream = if capture_mode == CaptureMode::SystemOnly || capture_mode == CaptureMode::Both {
-            use cpal::available_hosts;
-            
-            println!("[AUDIO] Attempting system audio capture...");
-            
-            // Try WASAPI host first
-            let wasapi_host = available_hosts()
-                .into_iter()
-                .find(|h| h.name().contains("WASAPI"))
-                .and_then(|id| cpal::host_from_id(id).ok());
-            
-            let host = wasapi_host.unwrap_or_else(|| {
-                println!("[AUDIO] WASAPI not available, using default host");
-                cpal::default_host()
-            });
-            
-            // Strategy 1: Try loopback from default output device
-            let loopback_result = host.default_output_device().and_then(|device| {
-                let name = device.name().unwrap_or_default();
-                println!("[AUDIO] Trying loopback on output device: {}", name);
-                
-                device.default_output_config().ok().and_then(|config| {
-                    let channels = config.channels();
-                    let sample_rate = config.sample_rate().0;
-                    
-                    let tx = audio_tx.clone();
-                    let buf = buffer.clone();
-                    let sil = silence_count.clone();
-                    let vol = volume.clone();
-                    
-                    device.build_input_stream(
-                        &config.into(),
-                        move |data: &[f32], _| {
-                            if data.is_empty() { return; }
-                            
-                            let mono = to_mono(data, channels);
-                            let resampled = decimate(mono, sample_rate, TARGET_SAMPLE_RATE);
-                            
-                            let rms = calculate_rms(&resampled);
-                            if let Ok(mut v) = vol.lock() { *v = rms; }
-                            
-                            if let Ok(mut count) = sil.lock() {
-                                if rms < SILENCE_THRESHOLD {
-                                    *count += 1;
-                                    if *count > SILENCE_SKIP_CHUNKS { return; }
-                                } else {
-                                    *count = 0;
-                                }
-                            }
-                            
-                            if let Ok(mut b) = buf.lock() {
-                                b.extend(resampled);
-                                while b.len() >= MICRO_CHUNK_SAMPLES {
-                                    let chunk: Vec<f32> = b.drain(..MICRO_CHUNK_SAMPLES).collect();
-                                    if let Some(ref tx) = tx {
-                                        let _ = tx.send(chunk);
-                                    }
-                                }
-                            }
-                        },
-                        |e| eprintln!("[AUDIO] Loopback stream error: {}", e),
-                        None
-                    ).ok()
-                })
-            });
-            
-            if loopback_result.is_some() {
-                println!("[AUDIO] ✓ WASAPI loopback stream created successfully");
-                loopback_result
-            } else {
-                // Strategy 2: Try finding Stereo Mix or similar virtual device
-                println!("[AUDIO] Loopback failed, searching for Stereo Mix...");
-                
-                let stereo_mix = host.input_devices().ok().and_then(|devices| {
-                    devices.into_iter().find(|d| {
-                        if let Ok(name) = d.name() {
-                            let name_lower = name.to_lowercase();
-                            name_lower.contains("stereo mix") ||
-                            name_lower.contains("what u hear") ||
-                            name_lower.contains("wave out") ||
-                            name_lower.contains("loopback")
-                        } else {
-                            false
-                        }
-                    })
-                });
-                
-                if let Some(device) = stereo_mix {
-                    let name = device.name().unwrap_or_default();
-                    println!("[AUDIO] Found virtual capture device: {}", name);
-                    
-                    device.default_input_config().ok().and_then(|config| {
-                        let channels = config.channels();
-                        let sample_rate = config.sample_rate().0;
-                        
-                        let tx = audio_tx.clone();
-                        let buf = buffer.clone();
-                        let sil = silence_count.clone();
-                        let vol = volume.clone();
-                        
-                        device.build_input_stream(
-                            &config.into(),
-                            move |data: &[f32], _| {
-                                if data.is_empty() { return; }
-                                
-                                let mono = to_mono(data, channels);
-                                let resampled = decimate(mono, sample_rate, TARGET_SAMPLE_RATE);
-                                
-                                let rms = calculate_rms(&resampled);
-                                if let Ok(mut v) = vol.lock() { *v = rms; }
-                                
-                                if let Ok(mut count) = sil.lock() {
-                                    if rms < SILENCE_THRESHOLD {
-                                        *count += 1;
-                                        if *count > SILENCE_SKIP_CHUNKS { return; }
-                                    } else {
-                                        *count = 0;
-                                    }
-                                }
-                                
-                                if let Ok(mut b) = buf.lock() {
-                                    b.extend(resampled);
-                                    while b.len() >= MICRO_CHUNK_SAMPLES {
-                                        let chunk: Vec<f32> = b.drain(..MICRO_CHUNK_SAMPLES).collect();
-                                        if let Some(ref tx) = tx {
-                                            let _ = tx.send(chunk);
-                                        }
-                                    }
-                                }
-                            },
-                            |e| eprintln!("[AUDIO] Stereo Mix error: {}", e),
-                            None
-                        ).ok()
-                    })
+        // === SYSTEM AUDIO (WASAPI loopback / Pulse monitor) ===
+        let system_queue: SourceQueue = Arc::new(Mutex::new(VecDeque::new()));
+        let system_result = if capture_mode == CaptureMode::SystemOnly || capture_mode == CaptureMode::Both {
+            Some(open_system_stream(output_id.as_deref(), &system_queue).map_err(CaptureError::NoSystemAudio))
+        } else { None };
+        
+        let mic_stream = match mic_result {
+            Some(Ok(stream)) => Some(stream),
+            Some(Err(e)) => {
+                eprintln!("[AUDIO] ✗ Mic: {}", e);
+                if matches!(system_result, Some(Ok(_))) {
+                    let _ = app.emit("god:audio_error", serde_json::json!({
+                        "code": e.code(),
+                        "message": e.to_string(),
+                        "fallback": "system",
+                    }));
                 } else {
-                    eprintln!("[AUDIO] ✗ System audio capture not available");
-                    eprintln!("[AUDIO] To enable system audio capture:");
-                    eprintln!("  1. Right-click Sound icon in system tray → Sounds");
-                    eprintln!("  2. Go to Recording tab");
-                    eprintln!("  3. Right-click → Show Disabled Devices");
-                    eprintln!("  4. Enable 'Stereo Mix' if available");
-                    None
+                    let _ = ready_tx.send(Err(e));
+                    return;
                 }
+                None
             }
-        } else { None };
+            None => None,
+        };
         
-        #[cfg(not(target_os = "windows"))]
-        let loopback_stream: Option<cpal::Stream> = None;
+        let loopback_stream = match system_result {
+            Some(Ok(stream)) => Some(stream),
+            Some(Err(e)) => {
+                eprintln!("[AUDIO] ✗ {}", e);
+                if mic_stream.is_some() {
+                    let _ = app.emit("god:audio_error", serde_json::json!({
+                        "code": e.code(),
+                        "message": e.to_string(),
+                        "fallback": "mic",
+                    }));
+                } else {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+                None
+            }
+            None => None,
+        };
         
         // Play streams
         if let Some(ref s) = mic_stream { 
@@ -347,20 +1128,31 @@ pub fn start_audio_capture(state: tauri::State<'_, AudioState>) -> Result<String
             }
         }
         
-        #[cfg(target_os = "windows")]
         if let Some(ref s) = loopback_stream { 
             if s.play().is_ok() {
                 println!("[AUDIO] ✓ Loopback stream active");
             }
         }
         
+        let _ = ready_tx.send(Ok("Capture started".to_string()));
         println!("[AUDIO] Capture running...");
-        let _ = stop_rx.recv();
+        run_mixer(
+            mic_stream.as_ref().map(|_| mic_queue),
+            loopback_stream.as_ref().map(|_| system_queue),
+            handles,
+            audio_tx,
+            stop_rx,
+        );
         println!("[AUDIO] Capture stopped");
     });
 
-    *is_rec = true;
-    Ok("Capture started".to_string())
+    let started = ready_rx.recv_timeout(Duration::from_secs(10))
+        .unwrap_or(Err(CaptureError::SetupTimeout));
+    if started.is_ok() {
+        *state.stream_control.lock()? = Some(stop_tx);
+        *is_rec = true;
+    }
+    started
 }
 
 #[tauri::command]
@@ -378,3 +1170,79 @@ pub fn stop_audio_capture(state: tauri::State<'_, AudioState>) -> Result<String,
     *is_rec = false;
     Ok("Stopped".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn sine(freq: f32, rate: u32, secs: f32) -> Vec<f32> {
+        let n = (rate as f32 * secs) as usize;
+        (0..n).map(|i| (2.0 * PI * freq * i as f32 / rate as f32).sin() * 0.5).collect()
+    }
+
+    /// Stream through the resampler in awkward block sizes, as a device callback would
+    fn resample_streaming(input: &[f32], from: u32) -> Vec<f32> {
+        let mut resampler = StreamResampler::new(from, TARGET_SAMPLE_RATE).unwrap();
+        let mut out = Vec::new();
+        for block in input.chunks(441) {
+            out.extend(resampler.process(block));
+        }
+        out.extend(resampler.flush());
+        out
+    }
+
+    /// Goertzel power of `freq` in `samples`, normalised by length
+    fn tone_power(samples: &[f32], freq: f32, rate: u32) -> f32 {
+        let coeff = 2.0 * (2.0 * PI * freq / rate as f32).cos();
+        let (mut s1, mut s2) = (0.0f32, 0.0f32);
+        for &x in samples {
+            let s0 = x + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        (s1 * s1 + s2 * s2 - coeff * s1 * s2) / (samples.len() as f32).powi(2)
+    }
+
+    #[test]
+    fn output_length_matches_rate_ratio() {
+        for rate in [44100, 48000, 96000] {
+            let input = sine(1000.0, rate, 1.0);
+            let out = resample_streaming(&input, rate);
+            assert_eq!(out.len(), 16000, "{} Hz input", rate);
+        }
+    }
+
+    #[test]
+    fn tone_frequency_is_preserved() {
+        for rate in [44100, 48000, 96000] {
+            let out = resample_streaming(&sine(1000.0, rate, 1.0), rate);
+            // Skip the filter's settling region at the edges
+            let body = &out[1000..15000];
+            let target = tone_power(body, 1000.0, TARGET_SAMPLE_RATE);
+            // Naive step_by(2) decimation of 44.1 kHz plays a 1 kHz tone back at ~1378 Hz
+            let shifted = tone_power(body, 1378.0, TARGET_SAMPLE_RATE);
+            assert!(target > 0.05, "{} Hz input: 1 kHz power {}", rate, target);
+            assert!(target > shifted * 100.0, "{} Hz input: tone shifted", rate);
+        }
+    }
+
+    #[test]
+    fn content_above_nyquist_is_rejected() {
+        for rate in [44100, 48000, 96000] {
+            let out = resample_streaming(&sine(10000.0, rate, 1.0), rate);
+            let body = &out[1000..15000];
+            // 10 kHz would alias to 6 kHz at 16 kHz without band-limiting
+            assert!(tone_power(body, 6000.0, TARGET_SAMPLE_RATE) < 1e-4, "{} Hz input aliased", rate);
+            assert!(calculate_rms(body) < 0.01, "{} Hz input leaked {}", rate, calculate_rms(body));
+        }
+    }
+
+    #[test]
+    fn matching_rates_pass_through() {
+        let input = sine(440.0, 16000, 0.1);
+        let mut resampler = StreamResampler::new(16000, 16000).unwrap();
+        assert_eq!(resampler.process(&input), input);
+        assert!(resampler.flush().is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+
+// ============================================================================
+// CONVERSATION CONTEXT - recent transcript sent as text with each request
+// ============================================================================
+
+/// Most entries ever kept; the configured count picks from these
+pub const CONTEXT_HISTORY_CAP: usize = 50;
+
+const CONTEXT_HEADER: &str = "CONTEXT - earlier in this meeting (do not transcribe again; reuse these speaker labels for the same voices):";
+
+/// Rough token count for budgeting, ~4 characters per token
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+#[derive(Debug, Clone)]
+struct ContextEntry {
+    speaker_id: String,
+    text: String,
+}
+
+/// Rolling window of emitted transcript plus every speaker label seen this session
+#[derive(Debug, Default)]
+pub struct ConversationContext {
+    entries: VecDeque<ContextEntry>,
+    roster: Vec<(String, usize)>, // Speaker label and turn count, in order of first appearance
+}
+
+impl ConversationContext {
+    pub fn record(&mut self, speaker_id: &str, text: &str) {
+        let text = text.trim();
+        if text.is_empty() { return; }
+
+        match self.roster.iter_mut().find(|(id, _)| id == speaker_id) {
+            Some((_, turns)) => *turns += 1,
+            None => self.roster.push((speaker_id.to_string(), 1)),
+        }
+        if self.entries.len() == CONTEXT_HISTORY_CAP {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ContextEntry { speaker_id: speaker_id.to_string(), text: text.to_string() });
+    }
+
+    /// Relabel a speaker everywhere after the user names them
+    pub fn rename(&mut self, from: &str, to: &str) {
+        for entry in self.entries.iter_mut().filter(|e| e.speaker_id == from) {
+            entry.speaker_id = to.to_string();
+        }
+        for (id, _) in self.roster.iter_mut().filter(|(id, _)| id == from) {
+            *id = to.to_string();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.roster.clear();
+    }
+
+    /// Context block with the roster and up to `max_entries` recent lines,
+    /// dropping the oldest lines (and trimming the start of the oldest one
+    /// kept) to stay within `token_budget`. Empty when there is nothing to send.
+    pub fn render(&self, max_entries: usize, token_budget: usize) -> String {
+        if self.entries.is_empty() || max_entries == 0 || token_budget == 0 {
+            return String::new();
+        }
+
+        let roster = self.roster.iter()
+            .map(|(id, turns)| format!("{} ({} turn{})", id, turns, if *turns == 1 { "" } else { "s" }))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let roster = format!("Speakers so far: {}", roster);
+
+        let mut remaining = token_budget.saturating_sub(estimate_tokens(CONTEXT_HEADER) + estimate_tokens(&roster));
+        let mut lines = Vec::new();
+        for entry in self.entries.iter().rev().take(max_entries) {
+            let line = format!("{}: {}", entry.speaker_id, entry.text);
+            let cost = estimate_tokens(&line);
+            if cost <= remaining {
+                remaining -= cost;
+                lines.push(line);
+                continue;
+            }
+            // Keep the end of the line, which leads into the current audio
+            let prefix = format!("{}: …", entry.speaker_id);
+            let room = remaining.saturating_sub(estimate_tokens(&prefix)) * 4;
+            if room >= 40 {
+                let chars: Vec<char> = entry.text.chars().collect();
+                let tail: String = chars[chars.len().saturating_sub(room)..].iter().collect();
+                lines.push(format!("{}{}", prefix, tail.trim_start()));
+            }
+            break;
+        }
+        if lines.is_empty() {
+            return String::new();
+        }
+
+        lines.reverse();
+        format!("{}\n{}\n{}", CONTEXT_HEADER, roster, lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(lines: &[(&str, &str)]) -> ConversationContext {
+        let mut context = ConversationContext::default();
+        for (speaker, text) in lines {
+            context.record(speaker, text);
+        }
+        context
+    }
+
+    #[test]
+    fn renders_roster_and_latest_entries_in_order() {
+        let context = context(&[("Speaker 1", "hello"), ("Speaker 2", "hi there"), ("Speaker 1", "let's start")]);
+        let text = context.render(2, 500);
+        assert!(text.contains("Speakers so far: Speaker 1 (2 turns), Speaker 2 (1 turn)"));
+        assert!(text.ends_with("Speaker 2: hi there\nSpeaker 1: let's start"));
+        assert!(!text.contains("hello"));
+    }
+
+    #[test]
+    fn stays_within_token_budget() {
+        let long = "word ".repeat(200);
+        let context = context(&[("Speaker 1", &long), ("Speaker 2", &long), ("Speaker 1", "short reply")]);
+        for budget in [60, 150, 400] {
+            let text = context.render(10, budget);
+            assert!(estimate_tokens(&text) <= budget + 2, "{} tokens over budget {}", estimate_tokens(&text), budget);
+            assert!(text.ends_with("Speaker 1: short reply"));
+        }
+    }
+
+    #[test]
+    fn empty_or_disabled_context_renders_nothing() {
+        assert_eq!(ConversationContext::default().render(8, 500), "");
+        let context = context(&[("Speaker 1", "hello")]);
+        assert_eq!(context.render(0, 500), "");
+        assert_eq!(context.render(8, 0), "");
+    }
+}
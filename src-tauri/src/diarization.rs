@@ -0,0 +1,542 @@
+use realfft::{RealFftPlanner, RealToComplex};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::gemini_client::GeminiState;
+use crate::session_manager::{load_json, save_json_atomic};
+use crate::voiceprints::VoiceprintStore;
+
+// ============================================================================
+// SPEAKER DIARIZATION - MFCC voice embeddings + online clustering
+// ============================================================================
+
+const SAMPLE_RATE: f32 = 16000.0;
+const FRAME_SAMPLES: usize = 400;        // 25 ms
+const HOP_SAMPLES: usize = 160;          // 10 ms
+const FFT_SIZE: usize = 512;
+const MEL_BANDS: usize = 26;
+const CEPSTRA: usize = 19;               // c1..c19; c0 is loudness, not voice
+pub const EMBEDDING_DIM: usize = CEPSTRA * 2; // Cepstral means, then standard deviations
+const MEL_RANGE_HZ: (f32, f32) = (100.0, 7600.0);
+const PAUSE_BELOW_PEAK_DB: f32 = 30.0;   // Frames this far under the loudest one are pauses
+const SILENCE_FLOOR_DB: f32 = -30.0;    // Frame energy of digital silence / line noise
+const MIN_VOICED_FRAMES: usize = 50;     // 0.5 s of voice for a usable embedding
+const MAX_NAME_CHARS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiarizationConfig {
+    pub enabled: bool,             // Off = keep the model's per-batch speaker guess
+    pub similarity_threshold: f32, // Cosine similarity needed to join an existing speaker
+    pub max_speakers: usize,       // Past this, new voices join the closest speaker
+    pub min_segment_secs: f32,     // Shorter segments may join a speaker but never start one
+    pub voiceprint_threshold: f32, // Similarity needed to name a speaker after an enrolled voice
+}
+
+impl Default for DiarizationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            similarity_threshold: 0.80,
+            max_speakers: 8,
+            min_segment_secs: 2.0,
+            voiceprint_threshold: 0.85,
+        }
+    }
+}
+
+impl DiarizationConfig {
+    const FILE: &'static str = "diarization_config.json";
+
+    pub fn load() -> Self {
+        load_json::<Self>(Self::FILE)
+            .filter(|c| c.validate().is_ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_json_atomic(Self::FILE, self)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.5..=0.99).contains(&self.similarity_threshold) {
+            return Err(format!("Similarity threshold out of range (0.5-0.99): {}", self.similarity_threshold));
+        }
+        if !(1..=20).contains(&self.max_speakers) {
+            return Err(format!("Max speakers out of range (1-20): {}", self.max_speakers));
+        }
+        if !(0.5..=10.0).contains(&self.min_segment_secs) {
+            return Err(format!("Min segment out of range (0.5-10s): {}", self.min_segment_secs));
+        }
+        if !(0.5..=0.99).contains(&self.voiceprint_threshold) {
+            return Err(format!("Voiceprint threshold out of range (0.5-0.99): {}", self.voiceprint_threshold));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// VOICE EMBEDDINGS
+// ============================================================================
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Fixed-size voice fingerprint of a stretch of 16 kHz speech: mean and
+/// spread of liftered MFCCs over voiced frames, L2-normalised so speakers
+/// compare by cosine similarity
+pub struct Embedder {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    filters: Vec<Vec<(usize, f32)>>, // Triangular mel filters as (bin, weight)
+    dct: Vec<Vec<f32>>,              // Rows c1..c19 of a DCT-II, liftered
+}
+
+impl Default for Embedder {
+    fn default() -> Self {
+        let window = (0..FRAME_SAMPLES)
+            .map(|i| 0.54 - 0.46 * (2.0 * std::f32::consts::PI * i as f32 / (FRAME_SAMPLES - 1) as f32).cos())
+            .collect();
+
+        let bins = FFT_SIZE / 2 + 1;
+        let (low, high) = (hz_to_mel(MEL_RANGE_HZ.0), hz_to_mel(MEL_RANGE_HZ.1));
+        let edges: Vec<f32> = (0..MEL_BANDS + 2)
+            .map(|i| mel_to_hz(low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32) * FFT_SIZE as f32 / SAMPLE_RATE)
+            .collect();
+        let filters = (0..MEL_BANDS)
+            .map(|band| {
+                let (left, center, right) = (edges[band], edges[band + 1], edges[band + 2]);
+                (0..bins)
+                    .filter_map(|bin| {
+                        let b = bin as f32;
+                        let weight = if b <= left || b >= right { 0.0 }
+                            else if b <= center { (b - left) / (center - left) }
+                            else { (right - b) / (right - center) };
+                        (weight > 0.0).then_some((bin, weight))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Sinusoidal lifter evens out the natural decay of higher coefficients
+        let lifter = |k: usize| 1.0 + 11.0 * (std::f32::consts::PI * k as f32 / 22.0).sin();
+        let dct = (1..=CEPSTRA)
+            .map(|k| {
+                (0..MEL_BANDS)
+                    .map(|n| lifter(k) * (std::f32::consts::PI * k as f32 * (n as f32 + 0.5) / MEL_BANDS as f32).cos())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            fft: RealFftPlanner::<f32>::new().plan_fft_forward(FFT_SIZE),
+            window,
+            filters,
+            dct,
+        }
+    }
+}
+
+impl Embedder {
+    /// `None` when there is too little voiced audio to say who is speaking
+    pub fn embed(&self, audio: &[f32]) -> Option<Vec<f32>> {
+        self.embed_voiced(audio).map(|(embedding, _)| embedding)
+    }
+
+    /// Embedding plus the seconds of voiced audio it was taken from; pauses and
+    /// silence around the speech don't count
+    pub fn embed_voiced(&self, audio: &[f32]) -> Option<(Vec<f32>, f32)> {
+        if audio.len() < FRAME_SAMPLES {
+            return None;
+        }
+
+        let mut input = self.fft.make_input_vec();
+        let mut spectrum = self.fft.make_output_vec();
+        let mut frames: Vec<(f32, Vec<f32>)> = Vec::new(); // (log energy, log mel bands)
+        for start in (0..=audio.len() - FRAME_SAMPLES).step_by(HOP_SAMPLES) {
+            input.iter_mut().for_each(|x| *x = 0.0);
+            for (i, (x, w)) in audio[start..start + FRAME_SAMPLES].iter().zip(&self.window).enumerate() {
+                input[i] = x * w;
+            }
+            if self.fft.process(&mut input, &mut spectrum).is_err() {
+                return None;
+            }
+            let power: Vec<f32> = spectrum.iter().map(|c| c.norm_sqr()).collect();
+            let energy: f32 = power.iter().sum();
+            let mel = self.filters.iter()
+                .map(|filter| filter.iter().map(|(bin, w)| power[*bin] * w).sum::<f32>().max(1e-10).ln())
+                .collect();
+            frames.push((10.0 * energy.max(1e-10).log10(), mel));
+        }
+
+        let peak = frames.iter().map(|(db, _)| *db).fold(f32::MIN, f32::max);
+        let voiced: Vec<&Vec<f32>> = frames.iter()
+            .filter(|(db, _)| *db > SILENCE_FLOOR_DB && *db > peak - PAUSE_BELOW_PEAK_DB)
+            .map(|(_, mel)| mel)
+            .collect();
+        if voiced.len() < MIN_VOICED_FRAMES {
+            return None;
+        }
+
+        let cepstra: Vec<Vec<f32>> = voiced.iter()
+            .map(|mel| self.dct.iter().map(|row| row.iter().zip(mel.iter()).map(|(a, b)| a * b).sum()).collect())
+            .collect();
+        let count = cepstra.len() as f32;
+        let mut embedding = vec![0.0f32; EMBEDDING_DIM];
+        for c in &cepstra {
+            for k in 0..CEPSTRA {
+                embedding[k] += c[k] / count;
+            }
+        }
+        for c in &cepstra {
+            for k in 0..CEPSTRA {
+                embedding[CEPSTRA + k] += (c[k] - embedding[k]).powi(2) / count;
+            }
+        }
+        for k in 0..CEPSTRA {
+            embedding[CEPSTRA + k] = embedding[CEPSTRA + k].sqrt();
+        }
+        normalize(&mut embedding);
+        Some((embedding, voiced.len() as f32 * HOP_SAMPLES as f32 / SAMPLE_RATE))
+    }
+}
+
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norms > 0.0 { dot / norms } else { 0.0 }
+}
+
+// ============================================================================
+// SPEAKER CLUSTERS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Speaker {
+    pub id: String,           // Stable for the session, e.g. "Speaker 2"
+    pub name: Option<String>, // Set by the user; replaces the id in transcripts
+    pub segments: u32,
+    pub speech_secs: f32,
+    pub recognized: Option<f32>, // Similarity to the enrolled voice that supplied `name`
+    #[serde(skip)]
+    centroid: Vec<f32>,
+    #[serde(skip)]
+    named_by_user: bool, // Enrolled voices never override the user's choice
+}
+
+impl Speaker {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
+}
+
+/// Session-wide speakers, grown online as segments arrive
+#[derive(Debug, Default)]
+pub struct SpeakerClusters {
+    speakers: Vec<Speaker>,
+}
+
+impl SpeakerClusters {
+    pub fn speakers(&self) -> &[Speaker] {
+        &self.speakers
+    }
+
+    pub fn clear(&mut self) {
+        self.speakers.clear();
+    }
+
+    /// Speaker for a segment's embedding: the closest one above the threshold,
+    /// else a new speaker. Segments shorter than `min_segment_secs` (and any
+    /// beyond `max_speakers`) join the closest speaker instead; `None` only when
+    /// a short segment arrives before anyone has been heard.
+    pub fn assign(&mut self, embedding: &[f32], secs: f32, config: &DiarizationConfig) -> Option<&Speaker> {
+        let best = self.speakers.iter()
+            .enumerate()
+            .map(|(i, s)| (i, cosine_similarity(&s.centroid, embedding)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let may_create = secs >= config.min_segment_secs && self.speakers.len() < config.max_speakers;
+        let index = match best {
+            Some((i, similarity)) if similarity >= config.similarity_threshold || !may_create => i,
+            _ if may_create => {
+                self.speakers.push(Speaker {
+                    id: format!("Speaker {}", self.speakers.len() + 1),
+                    name: None,
+                    segments: 0,
+                    speech_secs: 0.0,
+                    recognized: None,
+                    centroid: vec![0.0; embedding.len()],
+                    named_by_user: false,
+                });
+                self.speakers.len() - 1
+            }
+            _ => return None,
+        };
+
+        // Duration-weighted running mean keeps long turns from being swamped by short ones
+        let speaker = &mut self.speakers[index];
+        let weight = secs / (speaker.speech_secs + secs).max(f32::EPSILON);
+        for (c, e) in speaker.centroid.iter_mut().zip(embedding) {
+            *c += (e - *c) * weight;
+        }
+        normalize(&mut speaker.centroid);
+        speaker.segments += 1;
+        speaker.speech_secs += secs;
+        Some(&self.speakers[index])
+    }
+
+    /// Name an unnamed speaker after the enrolled voice their centroid matches.
+    /// Returns (old label, new label) when a name was given.
+    pub fn identify(&mut self, id: &str, voiceprints: &VoiceprintStore, threshold: f32) -> Option<(String, String)> {
+        let index = self.speakers.iter().position(|s| s.id == id)?;
+        let speaker = &self.speakers[index];
+        if speaker.name.is_some() || speaker.named_by_user { return None; }
+
+        let taken = |name: &str| self.speakers.iter().any(|s| s.label() == name);
+        let (profile, similarity) = voiceprints.identify(&speaker.centroid, threshold, taken)?;
+        let name = profile.name.clone();
+
+        let speaker = &mut self.speakers[index];
+        speaker.name = Some(name.clone());
+        speaker.recognized = Some(similarity);
+        Some((speaker.id.clone(), name))
+    }
+
+    /// Centroid and speech time of the speaker shown as `label`, for enrollment
+    pub fn voice(&self, label: &str) -> Option<(Vec<f32>, f32)> {
+        self.speakers.iter()
+            .find(|s| s.label() == label || s.id == label)
+            .map(|s| (s.centroid.clone(), s.speech_secs))
+    }
+
+    /// Name the speaker currently shown as `label` (its id or an earlier name).
+    /// An empty name restores the id. Returns (old label, new label).
+    pub fn rename(&mut self, label: &str, name: &str) -> Result<(String, String), String> {
+        let name = name.trim();
+        if name.chars().count() > MAX_NAME_CHARS {
+            return Err(format!("Speaker name too long (max {} characters)", MAX_NAME_CHARS));
+        }
+        let index = self.speakers.iter().position(|s| s.label() == label || s.id == label)
+            .ok_or_else(|| format!("Unknown speaker: {}", label))?;
+        if !name.is_empty() && self.speakers.iter().enumerate().any(|(i, s)| i != index && (s.label() == name || s.id == name)) {
+            return Err(format!("Another speaker is already called {}", name));
+        }
+
+        let speaker = &mut self.speakers[index];
+        let old = speaker.label().to_string();
+        speaker.name = (!name.is_empty()).then(|| name.to_string());
+        speaker.recognized = None;
+        speaker.named_by_user = true;
+        Ok((old, speaker.label().to_string()))
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[derive(Default)]
+pub struct DiarizationState {
+    pub config: Mutex<DiarizationConfig>,
+    pub speakers: Mutex<SpeakerClusters>,
+    pub embedder: Embedder,
+    pub voiceprints: Mutex<VoiceprintStore>,
+}
+
+impl DiarizationState {
+    /// Embedding for a finished segment, or `None` when diarization is off
+    pub fn embed_segment(&self, audio: &[f32]) -> Option<Vec<f32>> {
+        if !self.config.lock().unwrap().enabled { return None; }
+        self.embedder.embed(audio)
+    }
+
+    /// Session speaker label for a segment, `None` to keep the model's guess.
+    /// A speaker recognised from an enrolled voice is announced as renamed.
+    pub fn label(&self, app: &AppHandle, embedding: &[f32], secs: f32) -> Option<String> {
+        let config = *self.config.lock().unwrap();
+        let mut speakers = self.speakers.lock().unwrap();
+        let id = speakers.assign(embedding, secs, &config)?.id.clone();
+        let recognized = speakers.identify(&id, &self.voiceprints.lock().unwrap(), config.voiceprint_threshold);
+        if let Some((from, to)) = recognized {
+            announce_rename(app, &from, &to);
+        }
+        speakers.speakers().iter().find(|s| s.id == id).map(|s| s.label().to_string())
+    }
+}
+
+/// Relabel a speaker in the conversation context and tell the UI
+pub fn announce_rename(app: &AppHandle, from: &str, to: &str) {
+    if let Ok(mut context) = app.state::<GeminiState>().context.lock() {
+        context.rename(from, to);
+    }
+    println!("[DIARIZE] {} is now {}", from, to);
+    let _ = app.emit("god:speaker_renamed", serde_json::json!({ "from": from, "to": to }));
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_diarization_config(state: tauri::State<'_, DiarizationState>) -> Result<DiarizationConfig, String> {
+    Ok(*state.config.lock().map_err(|e| e.to_string())?)
+}
+
+/// Validates, persists and applies from the next segment
+#[tauri::command]
+pub fn update_diarization_config(
+    state: tauri::State<'_, DiarizationState>,
+    config: DiarizationConfig,
+) -> Result<DiarizationConfig, String> {
+    config.validate()?;
+    let mut current = state.config.lock().map_err(|e| e.to_string())?;
+    config.save()?;
+    *current = config;
+    println!("[DIARIZE] Config: {:?}", config);
+    Ok(config)
+}
+
+#[tauri::command]
+pub fn get_speakers(state: tauri::State<'_, DiarizationState>) -> Result<Vec<Speaker>, String> {
+    Ok(state.speakers.lock().map_err(|e| e.to_string())?.speakers().to_vec())
+}
+
+/// Renames a speaker of the running session. Emits `god:speaker_renamed` so
+/// transcripts already shown can be relabelled; labels the diarizer doesn't
+/// know (e.g. the model's own guesses) are relabelled the same way.
+#[tauri::command]
+pub fn rename_speaker(
+    state: tauri::State<'_, DiarizationState>,
+    app: AppHandle,
+    speaker: String,
+    name: String,
+) -> Result<String, String> {
+    let (from, to) = {
+        let mut speakers = state.speakers.lock().map_err(|e| e.to_string())?;
+        if speakers.speakers().iter().any(|s| s.label() == speaker || s.id == speaker) {
+            speakers.rename(&speaker, &name)?
+        } else {
+            let name = name.trim();
+            if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+                return Err(format!("Speaker name must be 1-{} characters", MAX_NAME_CHARS));
+            }
+            (speaker, name.to_string())
+        }
+    };
+    announce_rename(&app, &from, &to);
+    Ok(to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Vowel-like stand-in for a voice: harmonics of `f0` shaped by `formants` (centre, width)
+    fn voice(f0: f32, formants: &[(f32, f32)], secs: f32, seed: u32) -> Vec<f32> {
+        let syllable_hz = 3.0 + (seed % 5) as f32 * 0.4;
+        let mut phase = 0.0f32;
+        (0..(secs * SAMPLE_RATE) as usize).map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            let vibrato = 1.0 + 0.03 * (2.0 * std::f32::consts::PI * 4.0 * t).sin();
+            phase += 2.0 * std::f32::consts::PI * f0 * vibrato / SAMPLE_RATE;
+            let mut s = 0.0;
+            for k in (1..40).take_while(|k| f0 * *k as f32 <= 7800.0) {
+                let f = f0 * k as f32;
+                let gain: f32 = formants.iter().map(|(c, w)| (-((f - c) / w).powi(2)).exp()).sum();
+                s += gain * (phase * k as f32).sin() / (k as f32).sqrt();
+            }
+            let envelope = (0.5 + 0.5 * (2.0 * std::f32::consts::PI * syllable_hz * t).sin()).powi(2);
+            s * envelope * 0.2
+        }).collect()
+    }
+
+    const LOW: &[(f32, f32)] = &[(700.0, 300.0), (1200.0, 300.0), (2600.0, 400.0)];
+    const HIGH: &[(f32, f32)] = &[(400.0, 200.0), (2000.0, 350.0), (3000.0, 500.0)];
+
+    #[test]
+    fn same_voice_keeps_its_speaker_and_new_voice_gets_another() {
+        let embedder = Embedder::default();
+        let config = DiarizationConfig::default();
+        let mut clusters = SpeakerClusters::default();
+
+        let turns = [(120.0, LOW, 1), (210.0, HIGH, 3), (126.0, LOW, 7), (200.0, HIGH, 9)];
+        let ids: Vec<String> = turns.iter()
+            .map(|(f0, formants, seed)| {
+                let embedding = embedder.embed(&voice(*f0, formants, 3.0, *seed)).unwrap();
+                clusters.assign(&embedding, 3.0, &config).unwrap().id.clone()
+            })
+            .collect();
+        assert_eq!(ids, ["Speaker 1", "Speaker 2", "Speaker 1", "Speaker 2"]);
+        assert_eq!(clusters.speakers()[0].segments, 2);
+    }
+
+    #[test]
+    fn short_or_quiet_segments_never_start_a_speaker() {
+        let embedder = Embedder::default();
+        let config = DiarizationConfig::default();
+        let mut clusters = SpeakerClusters::default();
+
+        assert!(embedder.embed(&vec![0.0; 16000]).is_none());
+        let short = embedder.embed(&voice(120.0, LOW, 1.0, 1)).unwrap();
+        assert!(clusters.assign(&short, 1.0, &config).is_none());
+
+        let long = embedder.embed(&voice(120.0, LOW, 3.0, 2)).unwrap();
+        clusters.assign(&long, 3.0, &config).unwrap();
+        let other = embedder.embed(&voice(210.0, HIGH, 1.0, 3)).unwrap();
+        assert_eq!(clusters.assign(&other, 1.0, &config).unwrap().id, "Speaker 1");
+        assert_eq!(clusters.speakers().len(), 1);
+    }
+
+    #[test]
+    fn rename_replaces_label_and_rejects_duplicates() {
+        let config = DiarizationConfig::default();
+        let mut clusters = SpeakerClusters::default();
+        let mut a = vec![0.0; EMBEDDING_DIM];
+        a[0] = 1.0;
+        let mut b = vec![0.0; EMBEDDING_DIM];
+        b[1] = 1.0;
+        clusters.assign(&a, 3.0, &config);
+        clusters.assign(&b, 3.0, &config);
+
+        assert_eq!(clusters.rename("Speaker 1", " Ali ").unwrap(), ("Speaker 1".to_string(), "Ali".to_string()));
+        assert_eq!(clusters.assign(&a, 3.0, &config).unwrap().label(), "Ali");
+        assert!(clusters.rename("Speaker 2", "Ali").is_err());
+        assert_eq!(clusters.rename("Ali", "").unwrap().1, "Speaker 1");
+    }
+
+    #[test]
+    fn enrolled_voice_names_a_new_speaker_unless_user_named() {
+        let embedder = Embedder::default();
+        let config = DiarizationConfig::default();
+        let mut voiceprints = VoiceprintStore::default();
+        let enrolled = embedder.embed(&voice(118.0, LOW, 6.0, 4)).unwrap();
+        voiceprints.enroll("Omar", enrolled, 6.0, "omar.wav").unwrap();
+
+        let mut clusters = SpeakerClusters::default();
+        let stranger = embedder.embed(&voice(210.0, HIGH, 3.0, 1)).unwrap();
+        clusters.assign(&stranger, 3.0, &config);
+        assert_eq!(clusters.identify("Speaker 1", &voiceprints, config.voiceprint_threshold), None);
+
+        let omar = embedder.embed(&voice(124.0, LOW, 3.0, 2)).unwrap();
+        clusters.assign(&omar, 3.0, &config);
+        let renamed = clusters.identify("Speaker 2", &voiceprints, config.voiceprint_threshold);
+        assert_eq!(renamed, Some(("Speaker 2".to_string(), "Omar".to_string())));
+        assert!(clusters.speakers()[1].recognized.unwrap() >= config.voiceprint_threshold);
+
+        clusters.rename("Omar", "").unwrap();
+        assert_eq!(clusters.identify("Speaker 2", &voiceprints, config.voiceprint_threshold), None);
+    }
+}
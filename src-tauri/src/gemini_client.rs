@@ -1,10 +1,35 @@
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::{self, JoinError, JoinSet};
 use tokio::time::{Duration, interval, timeout, Instant, sleep};
 use crossbeam_channel::Receiver;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use futures_util::future::BoxFuture;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use crate::audio_capture::{AudioChunk, AudioState, StreamResampler, TARGET_SAMPLE_RATE};
+use crate::conversation_context::{ConversationContext, CONTEXT_HISTORY_CAP};
+use crate::diarization::{DiarizationState, SpeakerClusters};
+use crate::gemini_live::{run_live, LiveEvent, LiveInput, LiveSetup};
+use crate::intelligence_schema;
+use crate::prompt_templates::PromptState;
+use crate::llm_analysis::{reanalyze_response, AnalysisState};
+use crate::processing_engine::{
+    extract_json_objects, generate_optimistic, now_ms, process_intelligence, record_invalid_response,
+    repair_intelligence_output, ProcessingEngineState, ProcessingError, SegmentTiming,
+};
+use crate::transcription::{
+    ProviderCapabilities, ProviderError, ProviderModel, ProviderRegistry, Transcription,
+    TranscriptionProvider, TranscriptionRequest,
+};
+use crate::vad::{Vad, VadConfig};
+use crate::session_manager::{
+    load_json, save_json_atomic, GraphEdge as SessionGraphEdge, GraphNode as SessionGraphNode, SessionData,
+    SessionManager, TranscriptEntry,
+};
 
 // ============================================================================
 // GEMINI CLIENT - With Rate Limiting & Smart Batching
@@ -12,25 +37,18 @@ use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
 const GEMINI_REST_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
 
-// RATE LIMITING CONFIG
-const MIN_REQUEST_INTERVAL_SECS: u64 = 3;      // Minimum 3 seconds between requests
-const INITIAL_BACKOFF_SECS: u64 = 5;           // Start with 5 second backoff
-const MAX_BACKOFF_SECS: u64 = 60;              // Max 60 second backoff
 const RATE_LIMIT_CODES: [&str; 3] = ["429", "RESOURCE_EXHAUSTED", "rate"];
 
-// AUDIO BATCHING CONFIG
-const MIN_SPEECH_SECS: f32 = 3.0;              // Minimum 3 seconds of speech
-const SILENCE_TIMEOUT_SECS: f32 = 2.0;         // 2 seconds silence = end
-const MAX_BATCH_SECS: f32 = 15.0;              // Max 15 seconds per batch
-const SPEECH_THRESHOLD: f32 = 0.001;           // 0.001 to resolve "waiting for speech" (was 0.02)
-const SILENCE_THRESHOLD: f32 = 0.0005;         // Even lower for silence
-
 
 pub struct GeminiState {
-    pub audio_rx: StdMutex<Option<Receiver<Vec<f32>>>>,
+    pub audio_rx: StdMutex<Option<Receiver<AudioChunk>>>,
     pub api_key: StdMutex<Option<String>>,
     pub is_connected: StdMutex<bool>,
     pub selected_model: StdMutex<String>,
+    pub batching: StdMutex<AudioBatchingConfig>, // Read every tick by the running loop
+    pub metrics: StdMutex<PipelineMetrics>,
+    pub providers: ProviderRegistry, // The selected model picks the provider
+    pub context: StdMutex<ConversationContext>, // Emitted transcript of the current session
 }
 
 impl Default for GeminiState {
@@ -40,21 +58,116 @@ impl Default for GeminiState {
             api_key: StdMutex::new(None),
             is_connected: StdMutex::new(false),
             selected_model: StdMutex::new("gemini-2.5-flash-preview-09-2025".to_string()),
+            batching: StdMutex::new(AudioBatchingConfig::default()),
+            metrics: StdMutex::new(PipelineMetrics::default()),
+            providers: default_providers(),
+            context: StdMutex::new(ConversationContext::default()),
+        }
+    }
+}
+
+fn default_providers() -> ProviderRegistry {
+    let mut registry = ProviderRegistry::default();
+    registry.register(Arc::new(GeminiProvider::default()));
+    #[cfg(feature = "whisper")]
+    registry.register(Arc::new(crate::whisper_provider::WhisperProvider::default()));
+    registry
+}
+
+// ============================================================================
+// Batching & Rate Limit Config
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioBatchingConfig {
+    pub min_speech_secs: f32,          // Shorter segments are discarded
+    pub silence_timeout_secs: f32,     // Silence that ends a segment
+    pub max_batch_secs: f32,           // Force a send after this much audio
+    pub overlap_ms: u32,               // Audio repeated into the next batch after a forced split
+    pub min_request_interval_secs: u64, // Gap enforced between API requests
+    pub initial_backoff_secs: u64,     // First wait after a rate limit
+    pub max_backoff_secs: u64,         // Backoff doubles up to this
+    pub queue_capacity: usize,         // Segments waiting for upload before the oldest is dropped
+    pub max_concurrent_requests: usize, // Upload workers allowed in flight at once
+    pub context_entries: usize,        // Recent transcript lines sent with each request, 0 = none
+    pub context_token_budget: usize,   // Upper bound on the context text, roughly in tokens
+}
+
+impl Default for AudioBatchingConfig {
+    fn default() -> Self {
+        Self {
+            min_speech_secs: 3.0,
+            silence_timeout_secs: 2.0,
+            max_batch_secs: 15.0,
+            overlap_ms: 500,
+            min_request_interval_secs: 3,
+            initial_backoff_secs: 5,
+            max_backoff_secs: 60,
+            queue_capacity: 8,
+            max_concurrent_requests: 2,
+            context_entries: 8,
+            context_token_budget: 600,
         }
     }
 }
 
-const GOD_PROMPT_V9: &str = r#"You are a PASSIVE MEETING INTELLIGENCE ENGINE.
+impl AudioBatchingConfig {
+    const FILE: &'static str = "batching_config.json";
 
-OUTPUT FORMAT - JSON ONLY:
-{"transcript":"exact text","speaker":"Speaker 1","tone":"NEUTRAL","category":["INFO"],"confidence":0.85}
+    pub fn load() -> Self {
+        load_json::<Self>(Self::FILE)
+            .filter(|c| c.validate().is_ok())
+            .unwrap_or_default()
+    }
 
-RULES:
-- JSON only, no markdown
-- Transcribe accurately (English/Urdu/Hindi)
-- tone: NEUTRAL|URGENT|FRUSTRATED|EXCITED|POSITIVE|NEGATIVE
-- category: TASK|DECISION|DEADLINE|QUERY|ACTION_ITEM|RISK|INFO
-- If silence/unclear: {"status":"silence"}"#;
+    pub fn save(&self) -> Result<(), String> {
+        save_json_atomic(Self::FILE, self)
+    }
+
+    /// Never batch more audio than the provider accepts in one request
+    pub fn capped_to(mut self, max_segment_secs: f32) -> Self {
+        self.max_batch_secs = self.max_batch_secs.min(max_segment_secs);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.5..=30.0).contains(&self.min_speech_secs) {
+            return Err(format!("Min speech out of range (0.5-30s): {}", self.min_speech_secs));
+        }
+        if !(0.3..=10.0).contains(&self.silence_timeout_secs) {
+            return Err(format!("Silence timeout out of range (0.3-10s): {}", self.silence_timeout_secs));
+        }
+        if !(self.min_speech_secs..=60.0).contains(&self.max_batch_secs) {
+            return Err(format!("Max batch must be between min speech and 60s: {}", self.max_batch_secs));
+        }
+        if self.overlap_ms > 3000 || self.overlap_ms as f32 / 1000.0 >= self.max_batch_secs / 2.0 {
+            return Err(format!("Overlap must be under 3000ms and half the max batch: {}", self.overlap_ms));
+        }
+        if self.min_request_interval_secs > 60 {
+            return Err(format!("Request interval out of range (0-60s): {}", self.min_request_interval_secs));
+        }
+        if !(1..=300).contains(&self.initial_backoff_secs) {
+            return Err(format!("Initial backoff out of range (1-300s): {}", self.initial_backoff_secs));
+        }
+        if !(self.initial_backoff_secs..=600).contains(&self.max_backoff_secs) {
+            return Err(format!("Max backoff must be between initial backoff and 600s: {}", self.max_backoff_secs));
+        }
+        if !(1..=64).contains(&self.queue_capacity) {
+            return Err(format!("Queue capacity out of range (1-64): {}", self.queue_capacity));
+        }
+        if !(1..=8).contains(&self.max_concurrent_requests) {
+            return Err(format!("Concurrent requests out of range (1-8): {}", self.max_concurrent_requests));
+        }
+        if self.context_entries > CONTEXT_HISTORY_CAP {
+            return Err(format!("Context entries out of range (0-{}): {}", CONTEXT_HISTORY_CAP, self.context_entries));
+        }
+        if self.context_token_budget > 4000 {
+            return Err(format!("Context token budget out of range (0-4000): {}", self.context_token_budget));
+        }
+        Ok(())
+    }
+}
 
 // ============================================================================
 // Structs
@@ -88,7 +201,14 @@ struct SystemInstruction { parts: Vec<TextPart> }
 struct TextPart { text: String }
 
 #[derive(Serialize)]
-struct GenerationConfig { temperature: f32, max_output_tokens: i32 }
+struct GenerationConfig {
+    temperature: f32,
+    max_output_tokens: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_schema: Option<serde_json::Value>, // Constrains output to IntelligenceOutput
+}
 
 #[derive(Deserialize, Debug)]
 struct RestResponse {
@@ -112,129 +232,471 @@ struct ApiError { message: Option<String>, code: Option<i32> }
 // Audio Helpers
 // ============================================================================
 
+/// Canonical 44-byte header for 16 kHz mono 16-bit PCM holding `num_samples`
+pub(crate) fn wav_header(num_samples: u32) -> [u8; 44] {
+    let data_size = num_samples.saturating_mul(2);
+    let mut header = [0u8; 44];
+    header[0..4].copy_from_slice(b"RIFF");
+    header[4..8].copy_from_slice(&data_size.saturating_add(36).to_le_bytes());
+    header[8..12].copy_from_slice(b"WAVE");
+    header[12..16].copy_from_slice(b"fmt ");
+    header[16..20].copy_from_slice(&16u32.to_le_bytes());
+    header[20..22].copy_from_slice(&1u16.to_le_bytes());
+    header[22..24].copy_from_slice(&1u16.to_le_bytes());
+    header[24..28].copy_from_slice(&16000u32.to_le_bytes());
+    header[28..32].copy_from_slice(&32000u32.to_le_bytes());
+    header[32..34].copy_from_slice(&2u16.to_le_bytes());
+    header[34..36].copy_from_slice(&16u16.to_le_bytes());
+    header[36..40].copy_from_slice(b"data");
+    header[40..44].copy_from_slice(&data_size.to_le_bytes());
+    header
+}
+
+pub(crate) fn pcm16(sample: f32) -> [u8; 2] {
+    ((sample * 32767.0).clamp(-32768.0, 32767.0) as i16).to_le_bytes()
+}
+
 fn to_wav(samples: &[f32]) -> Vec<u8> {
-    let n = samples.len();
-    let data_size = (n * 2) as u32;
-    let mut wav = Vec::with_capacity(44 + n * 2);
-    
-    wav.extend_from_slice(b"RIFF");
-    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
-    wav.extend_from_slice(b"WAVE");
-    wav.extend_from_slice(b"fmt ");
-    wav.extend_from_slice(&16u32.to_le_bytes());
-    wav.extend_from_slice(&1u16.to_le_bytes());
-    wav.extend_from_slice(&1u16.to_le_bytes());
-    wav.extend_from_slice(&16000u32.to_le_bytes());
-    wav.extend_from_slice(&32000u32.to_le_bytes());
-    wav.extend_from_slice(&2u16.to_le_bytes());
-    wav.extend_from_slice(&16u16.to_le_bytes());
-    wav.extend_from_slice(b"data");
-    wav.extend_from_slice(&data_size.to_le_bytes());
-    
+    let mut wav = Vec::with_capacity(44 + samples.len() * 2);
+    wav.extend_from_slice(&wav_header(samples.len() as u32));
     for s in samples {
-        wav.extend_from_slice(&((*s * 32767.0).clamp(-32768.0, 32767.0) as i16).to_le_bytes());
+        wav.extend_from_slice(&pcm16(*s));
     }
     wav
 }
 
-fn rms(samples: &[f32]) -> f32 {
-    if samples.is_empty() { return 0.0; }
-    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+/// Per-batch energy of the separate mixer channels, used to attribute "me" vs "them"
+#[derive(Default)]
+struct SourceActivity {
+    mic_energy: f32,
+    system_energy: f32,
+}
+
+impl SourceActivity {
+    fn add(&mut self, chunk: &AudioChunk) {
+        if let Some(ref src) = chunk.sources {
+            self.mic_energy += src.mic.iter().map(|s| s * s).sum::<f32>();
+            self.system_energy += src.system.iter().map(|s| s * s).sum::<f32>();
+        }
+    }
+
+    fn mic_share(&self) -> Option<f32> {
+        let total = self.mic_energy + self.system_energy;
+        (total > f32::EPSILON).then(|| self.mic_energy / total)
+    }
+
+    /// Hint for the model, or empty when channels aren't split or neither side dominates
+    fn prompt_note(&self) -> &'static str {
+        let Some(mic_share) = self.mic_share() else { return "" };
+        if mic_share > 0.8 {
+            " (source: local microphone - the user, label as \"Me\")"
+        } else if mic_share < 0.2 {
+            " (source: system audio - remote participants)"
+        } else {
+            " (source: both local microphone and remote participants)"
+        }
+    }
+
+    /// Speaker for providers that don't guess one, when one side clearly dominates
+    fn speaker(&self) -> Option<&'static str> {
+        match self.mic_share()? {
+            share if share > 0.8 => Some("Me"),
+            share if share < 0.2 => Some("Remote"),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Speech Batching
+// ============================================================================
+
+/// Outcome of a batcher step once a speech segment has ended
+enum Batch {
+    Ready {
+        audio: Vec<f32>,
+        continues: bool, // Starts with the overlap tail of the previous batch
+    },
+    Discarded(f32), // Too short to send, duration in seconds
+}
+
+/// Speech segmentation shared by the live loop and file import. The caller
+/// supplies the clock: wall time when live, sample position when offline.
+#[derive(Default)]
+struct SpeechBatcher {
+    config: AudioBatchingConfig,
+    buffer: Vec<f32>,
+    speaking: bool,
+    speech_start: f32,
+    last_speech: f32,
+    carried: bool, // Buffer was seeded with overlap from a forced split
+}
+
+impl SpeechBatcher {
+    fn new(config: AudioBatchingConfig) -> Self {
+        Self { config, ..Default::default() }
+    }
+
+    /// Takes effect from the next push; audio already buffered is kept
+    fn set_config(&mut self, config: AudioBatchingConfig) {
+        self.config = config;
+    }
+
+    /// `is_speech` comes from the VAD, so hangover already bridges short pauses
+    fn push(&mut self, now: f32, block: Vec<f32>, is_speech: bool) -> Option<Batch> {
+        if is_speech {
+            if !self.speaking {
+                self.speaking = true;
+                self.speech_start = now;
+                println!("[AUDIO] Speech started");
+            }
+            self.last_speech = now;
+            self.buffer.extend(block);
+        } else if self.speaking {
+            self.buffer.extend(block);
+        }
+        
+        // Check if should process: a pause ends the segment, length forces a split
+        let duration = now - self.speech_start;
+        let silence = now - self.last_speech;
+        let ended = self.speaking
+            && duration >= self.config.min_speech_secs
+            && silence >= self.config.silence_timeout_secs;
+        let forced = self.speaking && !ended && duration >= self.config.max_batch_secs;
+        
+        let batch = if (ended || forced) && !self.buffer.is_empty() {
+            let batch = self.take();
+            if forced { self.carry_overlap(now, &batch); }
+            batch
+        } else { None };
+        
+        // Prevent buffer from growing too large
+        let max_samples = (self.config.max_batch_secs * 16000.0) as usize;
+        if self.buffer.len() > max_samples {
+            self.buffer.drain(0..self.buffer.len() - max_samples);
+        }
+        batch
+    }
+
+    /// End of input: hand over whatever speech is still buffered
+    fn finish(&mut self) -> Option<Batch> {
+        if self.buffer.is_empty() { None } else { self.take() }
+    }
+
+    fn take(&mut self) -> Option<Batch> {
+        let audio = std::mem::take(&mut self.buffer);
+        let continues = std::mem::take(&mut self.carried);
+        self.speaking = false;
+        let duration = audio.len() as f32 / 16000.0;
+        if duration >= self.config.min_speech_secs {
+            Some(Batch::Ready { audio, continues })
+        } else {
+            Some(Batch::Discarded(duration))
+        }
+    }
+
+    /// Speech is still going after a forced split: seed the next batch with the
+    /// tail of this one so a word on the boundary is heard whole at least once
+    fn carry_overlap(&mut self, now: f32, batch: &Option<Batch>) {
+        let Some(Batch::Ready { audio, .. }) = batch else { return };
+        let overlap = (self.config.overlap_ms as usize * 16).min(audio.len());
+        self.speaking = true;
+        self.speech_start = now - overlap as f32 / 16000.0;
+        if overlap > 0 {
+            self.buffer.extend_from_slice(&audio[audio.len() - overlap..]);
+            self.carried = true;
+        }
+    }
 }
 
 // ============================================================================
 // API Call with Rate Limiting
 // ============================================================================
 
-async fn call_gemini_with_backoff(
-    key: &str,
-    model: &str,
-    audio: &[f32],
-    backoff: &mut u64,
-    last_request: &mut Instant,
-) -> Result<String, String> {
-    // Enforce minimum interval
-    let elapsed = last_request.elapsed();
-    let min_interval = Duration::from_secs(MIN_REQUEST_INTERVAL_SECS);
-    if elapsed < min_interval {
-        let wait = min_interval - elapsed;
-        println!("[GEMINI] Rate limit: waiting {:.1}s", wait.as_secs_f32());
-        sleep(wait).await;
+#[derive(Default)]
+struct LimiterState {
+    next_slot: Option<Instant>,
+    backoff: u64,
+}
+
+/// Request pacing shared by every upload worker. Each caller reserves its own
+/// start slot, so concurrent requests still respect the minimum gap and backoff.
+#[derive(Default)]
+struct RateLimiter {
+    state: StdMutex<LimiterState>,
+}
+
+impl RateLimiter {
+    /// Reserve the next request slot and sleep until it arrives
+    async fn acquire(&self, limits: &AudioBatchingConfig) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            let slot = state.next_slot.map_or(now, |t| t.max(now)) + Duration::from_secs(state.backoff);
+            state.next_slot = Some(slot + Duration::from_secs(limits.min_request_interval_secs));
+            if state.backoff > 0 {
+                println!("[GEMINI] Backoff: {}s", state.backoff);
+            }
+            slot - now
+        };
+        if !wait.is_zero() {
+            println!("[GEMINI] Rate limit: waiting {:.1}s", wait.as_secs_f32());
+            sleep(wait).await;
+        }
     }
-    
-    // Apply backoff if we had errors
-    if *backoff > 0 {
-        println!("[GEMINI] Backoff: waiting {}s", backoff);
-        sleep(Duration::from_secs(*backoff)).await;
+
+    /// Exponential backoff, returns the new delay in seconds
+    fn rate_limited(&self, limits: &AudioBatchingConfig) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.backoff = (state.backoff * 2).max(limits.initial_backoff_secs).min(limits.max_backoff_secs);
+        state.backoff
     }
-    
-    *last_request = Instant::now();
-    
-    let wav = to_wav(audio);
-    let b64 = BASE64.encode(&wav);
-    
-    let request = RestRequest {
-        contents: vec![Content {
-            parts: vec![
-                Part { text: Some("Analyze this audio:".into()), inline_data: None },
-                Part { text: None, inline_data: Some(InlineData { 
-                    mime_type: "audio/wav".into(), 
-                    data: b64 
-                })},
-            ],
-        }],
-        system_instruction: Some(SystemInstruction {
-            parts: vec![TextPart { text: GOD_PROMPT_V9.into() }],
-        }),
-        generation_config: GenerationConfig { temperature: 0.1, max_output_tokens: 512 },
-    };
-    
-    let url = format!("{}/{}:generateContent?key={}", GEMINI_REST_URL, model, key);
-    
-    let client = reqwest::Client::new();
-    let response = client.post(&url)
-        .json(&request)
-        .timeout(Duration::from_secs(30))
-        .send()
-        .await
-        .map_err(|e| format!("HTTP: {}", e))?;
-    
-    let status = response.status();
-    let text = response.text().await.map_err(|e| format!("Read: {}", e))?;
-    
-    // Check for rate limiting
-    let is_rate_limited = status.as_u16() == 429 
-        || RATE_LIMIT_CODES.iter().any(|code| text.contains(code));
-    
-    if is_rate_limited {
-        // Exponential backoff
-        *backoff = (*backoff * 2).max(INITIAL_BACKOFF_SECS).min(MAX_BACKOFF_SECS);
-        println!("[GEMINI] ⚠️ Rate limited! Backoff now: {}s", backoff);
-        return Err(format!("Rate limited. Waiting {}s before retry.", backoff));
+
+    fn succeeded(&self) {
+        self.state.lock().unwrap().backoff = 0;
+    }
+
+    fn backoff(&self) -> u64 {
+        self.state.lock().unwrap().backoff
+    }
+}
+
+/// Paced call through the provider serving `request.model`. Rate limits feed the
+/// shared backoff; any other outcome clears it.
+async fn transcribe_with_limits(
+    provider: &dyn TranscriptionProvider,
+    request: TranscriptionRequest<'_>,
+    limits: &AudioBatchingConfig,
+    limiter: &RateLimiter,
+) -> Result<Transcription, ProviderError> {
+    if !provider.capabilities().offline {
+        limiter.acquire(limits).await;
     }
     
-    // Success - reset backoff
-    *backoff = 0;
-    
-    // Parse response
-    if let Ok(resp) = serde_json::from_str::<RestResponse>(&text) {
-        if let Some(error) = resp.error {
-            return Err(format!("API: {}", error.message.unwrap_or_default()));
-        }
-        if let Some(c) = resp.candidates.and_then(|c| c.into_iter().next()) {
-            if let Some(content) = c.content {
-                if let Some(parts) = content.parts {
-                    if let Some(part) = parts.into_iter().next() {
-                        if let Some(t) = part.text {
-                            return Ok(t);
+    match provider.transcribe(request).await {
+        Err(ProviderError::RateLimited(_)) => {
+            let backoff = limiter.rate_limited(limits);
+            println!("[GEMINI] ⚠️ Rate limited! Backoff now: {}s", backoff);
+            Err(ProviderError::RateLimited(format!("Waiting {}s before retry.", backoff)))
+        }
+        result => {
+            limiter.succeeded();
+            result
+        }
+    }
+}
+
+// ============================================================================
+// Gemini REST Provider
+// ============================================================================
+
+#[derive(Default)]
+pub struct GeminiProvider {
+    client: reqwest::Client,
+}
+
+impl GeminiProvider {
+    async fn generate(&self, request: TranscriptionRequest<'_>) -> Result<String, ProviderError> {
+        let wav = to_wav(request.audio);
+        let b64 = BASE64.encode(&wav);
+        
+        let mut parts = Vec::new();
+        if !request.context.is_empty() {
+            parts.push(Part { text: Some(request.context.to_string()), inline_data: None });
+        }
+        parts.push(Part { text: Some(format!("Analyze this audio{}:", request.source_note)), inline_data: None });
+        parts.push(Part { text: None, inline_data: Some(InlineData { 
+            mime_type: "audio/wav".into(), 
+            data: b64 
+        })});
+        
+        let body = RestRequest {
+            contents: vec![Content { parts }],
+            system_instruction: Some(SystemInstruction {
+                parts: vec![TextPart { text: request.system_prompt.to_string() }],
+            }),
+            generation_config: GenerationConfig {
+                temperature: 0.1,
+                max_output_tokens: 512,
+                response_mime_type: Some("application/json"),
+                response_schema: Some(intelligence_schema::response_schema()),
+            },
+        };
+        
+        let url = format!("{}/{}:generateContent?key={}", GEMINI_REST_URL, request.model, request.api_key);
+        
+        let response = self.client.post(&url)
+            .json(&body)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .map_err(|e| ProviderError::Failed(format!("HTTP: {}", e)))?;
+        
+        let status = response.status();
+        let text = response.text().await
+            .map_err(|e| ProviderError::Failed(format!("Read: {}", e)))?;
+        
+        // Check for rate limiting
+        if status.as_u16() == 429 || RATE_LIMIT_CODES.iter().any(|code| text.contains(code)) {
+            return Err(ProviderError::RateLimited(format!("HTTP {}", status)));
+        }
+        
+        // Parse response
+        if let Ok(resp) = serde_json::from_str::<RestResponse>(&text) {
+            if let Some(error) = resp.error {
+                return Err(ProviderError::Failed(format!("API: {}", error.message.unwrap_or_default())));
+            }
+            if let Some(c) = resp.candidates.and_then(|c| c.into_iter().next()) {
+                if let Some(content) = c.content {
+                    if let Some(parts) = content.parts {
+                        if let Some(part) = parts.into_iter().next() {
+                            if let Some(t) = part.text {
+                                return Ok(t);
+                            }
                         }
                     }
                 }
             }
         }
+        
+        Ok(text)
+    }
+
+    async fn ping(&self, api_key: &str, model: &str) -> Result<(), ProviderError> {
+        let url = format!("{}/{}:generateContent?key={}", GEMINI_REST_URL, model, api_key);
+        let r = self.client.post(&url)
+            .json(&serde_json::json!({"contents":[{"parts":[{"text":"OK"}]}]}))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| ProviderError::Failed(e.to_string()))?;
+        
+        let status = r.status();
+        match status.as_u16() {
+            429 => Err(ProviderError::RateLimited("429".into())),
+            403 => Err(ProviderError::Unauthorized("Quota exhausted".into())),
+            _ if !status.is_success() => Err(ProviderError::Failed(format!("HTTP {}", status))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `{"status":"silence"}` from prompt-only models, or an empty transcript when the
+/// response schema leaves no room for a status object
+fn is_silence_response(raw: &str) -> bool {
+    extract_json_objects(raw).0.first()
+        .map(|v| {
+            v.get("status").and_then(|s| s.as_str()) == Some("silence")
+                || v.get("transcript_chunk").and_then(|t| t.as_str()).is_some_and(|t| t.trim().is_empty())
+        })
+        .unwrap_or(false)
+}
+
+/// Read GOD schema JSON from a batch or Live response, repairing fences,
+/// truncation and loose field names on the way
+fn parse_response(raw: &str) -> Result<Transcription, ProviderError> {
+    if is_silence_response(raw) {
+        return Ok(Transcription::default());
+    }
+    let (output, report) = repair_intelligence_output(raw).map_err(ProviderError::InvalidResponse)?;
+    if !report.is_clean() {
+        println!("[GEMINI] Repaired response: {}", report);
+    }
+    Ok(output.into())
+}
+
+impl TranscriptionProvider for GeminiProvider {
+    fn id(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            structured_output: true,
+            requires_api_key: true,
+            offline: false,
+            max_segment_secs: 60.0,
+        }
+    }
+
+    fn models(&self) -> Vec<ProviderModel> {
+        [
+            ("gemini-2.5-flash-preview-09-2025", "⚡ Gemini 2.5 Flash"),
+            ("gemini-2.5-flash-lite-preview-09-2025", "🔥 Gemini 2.5 Flash Lite"),
+            ("gemini-3-flash-preview", "💎 Gemini 3 Flash"),
+            ("gemini-live-2.5-flash-preview", "🎙️ Gemini Live (streaming)"),
+        ]
+        .into_iter()
+        .map(|(id, name)| ProviderModel { id: id.into(), name: name.into() })
+        .collect()
+    }
+
+    fn transcribe<'a>(&'a self, request: TranscriptionRequest<'a>) -> BoxFuture<'a, Result<Transcription, ProviderError>> {
+        Box::pin(async move { parse_response(&self.generate(request).await?) })
+    }
+
+    fn check<'a>(&'a self, api_key: &'a str, model: &'a str) -> BoxFuture<'a, Result<(), ProviderError>> {
+        Box::pin(self.ping(api_key, model))
+    }
+}
+
+// ============================================================================
+// Response Routing
+// ============================================================================
+
+/// Push a transcription through the processing engine and emit the result.
+/// `voice` is the segment's speaker embedding; when present the diarizer's
+/// session label replaces the model's speaker guess.
+/// Returns the emitted transcript so an overlapping next batch can be de-duplicated.
+fn dispatch_response(
+    app: &AppHandle,
+    transcription: Transcription,
+    previous_transcript: Option<&str>,
+    timing: SegmentTiming,
+    voice: Option<&[f32]>,
+) -> Option<String> {
+    if transcription.is_silence() {
+        println!("[GEMINI] Silence reported, skipping");
+        return None;
     }
     
-    Ok(text)
+    let engine = app.state::<ProcessingEngineState>();
+    match process_intelligence(&engine, transcription, previous_transcript) {
+        Ok(Some(mut output)) => {
+            output.timestamp_ms = timing.wall_start_ms;
+            output.segment = Some(timing);
+            let secs = timing.end_ms.saturating_sub(timing.start_ms) as f32 / 1000.0;
+            if let Some(label) = voice.and_then(|v| app.state::<DiarizationState>().label(app, v, secs)) {
+                output.speaker_id = label;
+            }
+            println!("[ENGINE] ✓ #{} {} ({:?})", timing.sequence, output.speaker_id, output.intelligence.category);
+            app.state::<GeminiState>().context.lock().unwrap().record(&output.speaker_id, &output.transcript_chunk);
+            let _ = app.emit("god:intelligence", &output);
+            return Some(output.transcript_chunk);
+        }
+        Ok(None) => {
+            println!("[ENGINE] Filtered by confidence/category settings");
+        }
+        Err(e) => report_processing_error(app, e),
+    }
+    None
+}
+
+/// Log an engine rejection and tell the UI; a long run of bad responses resets the streak
+fn report_processing_error(app: &AppHandle, error: ProcessingError) {
+    match error {
+        ProcessingError::ErrorStreakExceeded(n) => {
+            println!("[ENGINE] ✗ {} invalid responses in a row", n);
+            *app.state::<ProcessingEngineState>().error_streak.lock().unwrap() = 0;
+            let _ = app.emit("god:status", format!("Error: {} invalid responses in a row", n));
+        }
+        e => {
+            println!("[ENGINE] ✗ Rejected response: {}", e);
+            let _ = app.emit("god:processing_error", serde_json::json!({
+                "message": e.to_string(),
+            }));
+        }
+    }
 }
 
 // ============================================================================
@@ -255,47 +717,33 @@ pub async fn test_gemini_connection(
     
     println!("========================================");
     println!("[GEMINI] Model: {}", m);
+    let limits = *state.batching.lock().unwrap();
     println!("[GEMINI] Rate limits: {}s min interval, {}s initial backoff", 
-             MIN_REQUEST_INTERVAL_SECS, INITIAL_BACKOFF_SECS);
+             limits.min_request_interval_secs, limits.initial_backoff_secs);
     println!("========================================");
     
+    let provider = state.providers.resolve(&m)
+        .ok_or_else(|| format!("Unknown model: {}", m))?;
     let _ = app.emit("god:status", "Testing...");
     
     // Quick test
-    let url = format!("{}/{}:generateContent?key={}", GEMINI_REST_URL, m, key);
-    let client = reqwest::Client::new();
-    
-    match client.post(&url)
-        .json(&serde_json::json!({"contents":[{"parts":[{"text":"OK"}]}]}))
-        .timeout(Duration::from_secs(10))
-        .send().await 
-    {
-        Ok(r) => {
-            let status = r.status();
-            let t = r.text().await.unwrap_or_default();
-            
-            if status.as_u16() == 429 {
-                println!("[GEMINI] Rate limited (429)");
-                let _ = app.emit("god:status", "Rate limited");
-                return Err("Rate limited".into());
-            } else if status.as_u16() == 403 {
-                println!("[GEMINI] Quota exhausted (403)");
-                let _ = app.emit("god:status", "Quota exhausted");
-                return Err("Quota exhausted".into());
-            } else if !status.is_success() {
-                println!("[GEMINI] HTTP error: {}", status);
-                let _ = app.emit("god:status", format!("HTTP {}", status));
-                return Err(format!("HTTP {}", status));
-            }
-            
+    match provider.check(&key, &m).await {
+        Ok(()) => {
             // Success - connected
-            println!("[GEMINI] Connection test passed");
+            println!("[GEMINI] Connection test passed ({})", provider.id());
             *state.is_connected.lock().unwrap() = true;
             let _ = app.emit("god:status", "Connected ✓");
         }
         Err(e) => {
-            let _ = app.emit("god:status", format!("Failed: {}", e));
-            return Err(e.to_string());
+            let message = match &e {
+                ProviderError::RateLimited(_) => "Rate limited".to_string(),
+                ProviderError::Unauthorized(msg)
+                | ProviderError::InvalidResponse(msg)
+                | ProviderError::Failed(msg) => msg.clone(),
+            };
+            println!("[GEMINI] Connection test failed: {}", e);
+            let _ = app.emit("god:status", format!("Failed: {}", message));
+            return Err(message);
         }
     }
     
@@ -317,150 +765,982 @@ pub fn update_gemini_key(state: tauri::State<'_, GeminiState>, key: String) -> R
     Ok(())
 }
 
+// ============================================================================
+// Upload Queue - decouples capture from in-flight requests
+// ============================================================================
+
+/// Counters surfaced to the UI so backpressure is visible instead of silent
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PipelineMetrics {
+    pub segments_queued: u64,
+    pub segments_dropped: u64,
+    pub dropped_audio_secs: f32,
+    pub requests_completed: u64,
+    pub requests_failed: u64,
+    pub queue_depth: usize,
+    pub in_flight: usize,
+}
+
+struct QueuedSegment {
+    timing: SegmentTiming,
+    audio: Vec<f32>,
+    continues: bool,
+    source_note: &'static str,
+    speaker_hint: Option<&'static str>,
+}
+
+/// Bounded FIFO between the batcher and the upload workers. When full, the
+/// oldest segment is dropped so the UI stays close to real time.
+#[derive(Default)]
+struct SegmentQueue {
+    segments: StdMutex<VecDeque<QueuedSegment>>,
+    ready: Notify,
+}
+
+impl SegmentQueue {
+    /// Returns the segment evicted to make room, if any
+    fn push(&self, segment: QueuedSegment, capacity: usize) -> (usize, Option<QueuedSegment>) {
+        let mut segments = self.segments.lock().unwrap();
+        let dropped = if segments.len() >= capacity.max(1) { segments.pop_front() } else { None };
+        segments.push_back(segment);
+        let depth = segments.len();
+        drop(segments);
+        self.ready.notify_one();
+        (depth, dropped)
+    }
+
+    async fn pop(&self) -> (usize, QueuedSegment) {
+        loop {
+            {
+                let mut segments = self.segments.lock().unwrap();
+                if let Some(segment) = segments.pop_front() {
+                    return (segments.len(), segment);
+                }
+            }
+            self.ready.notified().await;
+        }
+    }
+}
+
+/// What an upload hands to the delivery stage
+enum Delivery {
+    Response(Transcription, Option<Vec<f32>>), // With the segment's voice embedding
+    Skipped, // Dropped, failed or never sent - only advances the sequence
+}
+
+/// Sends delivered responses through the engine and on to the UI
+fn dispatch_to(app: &AppHandle) -> impl FnMut(Transcription, Option<&str>, SegmentTiming, Option<&[f32]>) -> Option<String> + '_ {
+    move |transcription, previous, timing, voice| dispatch_response(app, transcription, previous, timing, voice)
+}
+
+struct DeliveryState {
+    next: u32,
+    pending: BTreeMap<u32, (SegmentTiming, bool, Delivery)>,
+    last_transcript: Option<String>,
+}
+
+/// Releases results strictly in capture order, however the uploads finish.
+/// Every sequence number must be delivered, even when skipped; the first
+/// delivery of a sequence wins and later duplicates are ignored.
+struct OrderedDelivery {
+    state: StdMutex<DeliveryState>,
+}
+
+impl OrderedDelivery {
+    fn new(first_sequence: u32) -> Self {
+        Self {
+            state: StdMutex::new(DeliveryState {
+                next: first_sequence,
+                pending: BTreeMap::new(),
+                last_transcript: None,
+            }),
+        }
+    }
+
+    /// Responses next in line go to `sink` with the previous transcript when they
+    /// overlap it; the sink returns the transcript it emitted
+    fn deliver(
+        &self,
+        timing: SegmentTiming,
+        continues: bool,
+        outcome: Delivery,
+        mut sink: impl FnMut(Transcription, Option<&str>, SegmentTiming, Option<&[f32]>) -> Option<String>,
+    ) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        if timing.sequence < state.next {
+            return;
+        }
+        state.pending.entry(timing.sequence).or_insert((timing, continues, outcome));
+        
+        while let Some((timing, continues, outcome)) = state.pending.remove(&state.next) {
+            state.next += 1;
+            state.last_transcript = match outcome {
+                Delivery::Response(transcription, voice) => {
+                    let previous = if continues { state.last_transcript.as_deref() } else { None };
+                    sink(transcription, previous, timing, voice.as_deref())
+                }
+                Delivery::Skipped => None,
+            };
+        }
+        if !state.pending.is_empty() {
+            println!("[AUDIO] Holding {} result(s) until #{} arrives", state.pending.len(), state.next);
+        }
+    }
+}
+
+/// Apply a change to the shared metrics and push the snapshot to the UI
+fn update_metrics(app: &AppHandle, change: impl FnOnce(&mut PipelineMetrics)) {
+    let state = app.state::<GeminiState>();
+    let snapshot = {
+        let mut metrics = state.metrics.lock().unwrap();
+        change(&mut metrics);
+        metrics.clone()
+    };
+    let _ = app.emit("god:pipeline_metrics", &snapshot);
+}
+
 // ============================================================================
 // Smart Audio Loop with Rate Limiting
 // ============================================================================
 
-async fn smart_audio_loop(rx: Receiver<Vec<f32>>, app: AppHandle) {
+/// Capture side: drains the audio channel every tick and never waits on the network
+async fn smart_audio_loop(rx: Receiver<AudioChunk>, app: AppHandle) {
+    let mut config = *app.state::<GeminiState>().batching.lock().unwrap();
     println!("[AUDIO] Loop started - Min {}s speech, {}s silence timeout", 
-             MIN_SPEECH_SECS, SILENCE_TIMEOUT_SECS);
+             config.min_speech_secs, config.silence_timeout_secs);
     
     let _ = app.emit("god:status", "Listening...");
     
-    let mut buffer: Vec<f32> = Vec::new();
-    let mut speaking = false;
-    let mut speech_start: Option<Instant> = None;
-    let mut last_speech: Option<Instant> = None;
-    let mut processing = false;
+    let queue = Arc::new(SegmentQueue::default());
+    let delivery = Arc::new(OrderedDelivery::new(1));
+    tokio::spawn(upload_loop(app.clone(), queue.clone(), delivery.clone()));
     
-    // Rate limiting state
-    let mut backoff: u64 = 0;
-    let mut last_request = Instant::now() - Duration::from_secs(MIN_REQUEST_INTERVAL_SECS);
-    let mut request_count = 0u32;
+    let mut batcher = SpeechBatcher::new(config);
+    let mut activity = SourceActivity::default();
+    let clock = Instant::now();
+    let wall_origin = now_ms();
+    let sequence = Arc::new(AtomicU32::new(0)); // Shared with Live turns so UI keys stay unique
+    let mut live: Option<LiveHandle> = None;
+    let mut model = String::new();
+    let mut segment_limit = f32::MAX; // Longest request the selected provider accepts
     
     let mut tick = interval(Duration::from_millis(100));
     
     loop {
         tick.tick().await;
         
-        if processing { continue; }
+        let (selected, key) = {
+            let state = app.state::<GeminiState>();
+            let m = state.selected_model.lock().unwrap().clone();
+            let k = state.api_key.lock().unwrap().clone().unwrap_or_default();
+            (m, k)
+        };
+        if selected != model {
+            segment_limit = app.state::<GeminiState>().providers.resolve(&selected)
+                .map_or(f32::MAX, |p| p.capabilities().max_segment_secs);
+            model = selected;
+        }
+        
+        // Pick up config changes without restarting capture
+        let latest = app.state::<GeminiState>().batching.lock().unwrap().capped_to(segment_limit);
+        if latest != config {
+            println!("[AUDIO] Batching config updated: {:?}", latest);
+            config = latest;
+            batcher.set_config(config);
+        }
         
         // Collect audio
         let mut new: Vec<f32> = Vec::new();
-        while let Ok(s) = rx.try_recv() { new.extend(s); }
-        if new.is_empty() { continue; }
+        let mut is_speech = false;
+        while let Ok(chunk) = rx.try_recv() {
+            activity.add(&chunk);
+            is_speech |= chunk.is_speech;
+            new.extend(chunk.samples);
+        }
         
-        let level = rms(&new);
+        // Live models stream instead of batching; switching model switches mode
+        if !is_live_model(&model) || live.as_ref().is_some_and(|l| l.model != model || l.key != key) {
+            live = None;
+        }
+        if is_live_model(&model) && live.is_none() && !key.is_empty() {
+            let session = LiveSession { clock, wall_origin, sequence: sequence.clone(), delivery: delivery.clone() };
+            live = Some(LiveHandle::start(app.clone(), &key, &model, session));
+        }
+        if let Some(handle) = live.as_mut() {
+            handle.push(new, is_speech, clock.elapsed().as_millis() as u64);
+            activity = SourceActivity::default();
+            continue;
+        }
         
-        // Speech detection
-        if level > SPEECH_THRESHOLD {
-            if !speaking {
-                speaking = true;
-                speech_start = Some(Instant::now());
-                println!("[AUDIO] Speech started");
+        // Push every tick, even empty: the mixer goes quiet during pauses and
+        // the silence timeout has to keep advancing to end the segment
+        let (audio, continues) = match batcher.push(clock.elapsed().as_secs_f32(), new, is_speech) {
+            Some(Batch::Ready { audio, continues }) => (audio, continues),
+            Some(Batch::Discarded(duration)) => {
+                println!("[AUDIO] Discarding short segment ({:.1}s)", duration);
+                activity = SourceActivity::default();
+                continue;
+            }
+            None => continue,
+        };
+        
+        let request = sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        let duration = audio.len() as f32 / 16000.0;
+        let end_ms = clock.elapsed().as_millis() as u64;
+        let start_ms = end_ms.saturating_sub((duration * 1000.0) as u64);
+        let segment = QueuedSegment {
+            timing: SegmentTiming {
+                sequence: request,
+                start_ms,
+                end_ms,
+                wall_start_ms: wall_origin + start_ms,
+                wall_end_ms: wall_origin + end_ms,
+            },
+            audio,
+            continues,
+            source_note: activity.prompt_note(),
+            speaker_hint: activity.speaker(),
+        };
+        activity = SourceActivity::default();
+        
+        let (depth, dropped) = queue.push(segment, config.queue_capacity);
+        println!("[AUDIO] Queued {:.1}s as request #{} (queue {})", duration, request, depth);
+        update_metrics(&app, |m| {
+            m.segments_queued += 1;
+            m.queue_depth = depth;
+        });
+        
+        if let Some(dropped) = dropped {
+            let dropped_secs = dropped.audio.len() as f32 / 16000.0;
+            println!("[AUDIO] ✗ Queue full - dropped request #{} ({:.1}s)", dropped.timing.sequence, dropped_secs);
+            update_metrics(&app, |m| {
+                m.segments_dropped += 1;
+                m.dropped_audio_secs += dropped_secs;
+            });
+            let _ = app.emit("god:segment_dropped", serde_json::json!({
+                "request": dropped.timing.sequence,
+                "duration": dropped_secs,
+                "reason": "queue_full",
+            }));
+            delivery.deliver(dropped.timing, dropped.continues, Delivery::Skipped, dispatch_to(&app));
+        }
+    }
+}
+
+/// Upload side: keeps up to `max_concurrent_requests` requests in flight, all
+/// paced by one shared rate limiter
+async fn upload_loop(app: AppHandle, queue: Arc<SegmentQueue>, delivery: Arc<OrderedDelivery>) {
+    let limiter = Arc::new(RateLimiter::default());
+    let mut workers = JoinSet::new();
+    let mut uploads = HashMap::new(); // Task id -> the segment it is uploading
+    
+    loop {
+        while let Some(joined) = workers.try_join_next_with_id() {
+            settle_upload(&app, &delivery, &mut uploads, joined);
+        }
+        let max_workers = app.state::<GeminiState>().batching.lock().unwrap().max_concurrent_requests.max(1);
+        while workers.len() >= max_workers {
+            if let Some(joined) = workers.join_next_with_id().await {
+                settle_upload(&app, &delivery, &mut uploads, joined);
             }
-            last_speech = Some(Instant::now());
-            buffer.extend(new);
-        } else if level > SILENCE_THRESHOLD && speaking {
-            buffer.extend(new);
-            last_speech = Some(Instant::now());
-        } else if speaking {
-            buffer.extend(new);
         }
         
-        // Check if should process
-        let should_process = if speaking {
-            let duration = speech_start.map(|s| s.elapsed().as_secs_f32()).unwrap_or(0.0);
-            let silence = last_speech.map(|s| s.elapsed().as_secs_f32()).unwrap_or(0.0);
+        let (depth, segment) = queue.pop().await;
+        update_metrics(&app, |m| m.queue_depth = depth);
+        let slot = (segment.timing, segment.continues);
+        let handle = workers.spawn(upload_segment(app.clone(), segment, limiter.clone(), delivery.clone()));
+        uploads.insert(handle.id(), slot);
+    }
+}
+
+/// Skip the segment of an upload that panicked; it never delivered its sequence,
+/// so every later result would otherwise be held back forever
+fn settle_upload(
+    app: &AppHandle,
+    delivery: &OrderedDelivery,
+    uploads: &mut HashMap<task::Id, (SegmentTiming, bool)>,
+    joined: Result<(task::Id, ()), JoinError>,
+) {
+    if let Some((timing, continues)) = panicked_upload(uploads, joined) {
+        update_metrics(app, |m| m.requests_failed += 1);
+        delivery.deliver(timing, continues, Delivery::Skipped, dispatch_to(app));
+    }
+}
+
+/// Forget a finished upload, returning its segment if the task panicked
+fn panicked_upload(
+    uploads: &mut HashMap<task::Id, (SegmentTiming, bool)>,
+    joined: Result<(task::Id, ()), JoinError>,
+) -> Option<(SegmentTiming, bool)> {
+    let id = match &joined {
+        Ok((id, ())) => *id,
+        Err(e) => e.id(),
+    };
+    let slot = uploads.remove(&id)?;
+    let error = joined.err()?;
+    eprintln!("[GEMINI] ✗ Upload of request #{} failed: {}", slot.0.sequence, error);
+    Some(slot)
+}
+
+async fn upload_segment(
+    app: AppHandle,
+    segment: QueuedSegment,
+    limiter: Arc<RateLimiter>,
+    delivery: Arc<OrderedDelivery>,
+) {
+    let duration = segment.audio.len() as f32 / 16000.0;
+    let sequence = segment.timing.sequence;
+    
+    // Get current key, model, provider and limits from state
+    let (key, provider, model, config) = {
+        let state = app.state::<GeminiState>();
+        let k: String = state.api_key.lock().unwrap().clone().unwrap_or_default();
+        let m = state.selected_model.lock().unwrap().clone();
+        let c = *state.batching.lock().unwrap();
+        (k, state.providers.resolve(&m), m, c)
+    };
+    let Some(provider) = provider else {
+        println!("[GEMINI] ✗ Error: No provider serves model {}", model);
+        let _ = app.emit("god:status", format!("Error: Unknown model {}", model));
+        update_metrics(&app, |m| m.requests_failed += 1);
+        delivery.deliver(segment.timing, segment.continues, Delivery::Skipped, dispatch_to(&app));
+        return;
+    };
+
+    if key.is_empty() && provider.capabilities().requires_api_key {
+        println!("[GEMINI] ✗ Error: No API key configured");
+        let _ = app.emit("god:status", "Error: No API key");
+        let _ = app.emit("god:api_error", serde_json::json!({"code": 401, "message": "No API key configured"}));
+        update_metrics(&app, |m| m.requests_failed += 1);
+        delivery.deliver(segment.timing, segment.continues, Delivery::Skipped, dispatch_to(&app));
+        return;
+    }
+    
+    update_metrics(&app, |m| m.in_flight += 1);
+    let _ = app.emit("god:status", format!("Processing {:.1}s (#{})...", duration, sequence));
+    println!("[AUDIO] Processing {:.1}s, request #{}", duration, sequence);
+    
+    let system_prompt = app.state::<PromptState>().render();
+    let context = app.state::<GeminiState>().context.lock().unwrap()
+        .render(config.context_entries, config.context_token_budget);
+    let request = TranscriptionRequest {
+        api_key: &key,
+        model: &model,
+        audio: &segment.audio,
+        source_note: segment.source_note,
+        system_prompt: &system_prompt,
+        context: &context,
+    };
+    let result = transcribe_with_limits(provider.as_ref(), request, &config, &limiter).await;
+    match result {
+        Ok(mut transcription) => {
+            println!("[GEMINI] ✓ Response received (#{})", sequence);
+            if transcription.speaker_id.is_none() {
+                transcription.speaker_id = segment.speaker_hint.map(String::from);
+            }
+            let transcription = reanalyze_response(&app.state::<AnalysisState>(), transcription).await;
+            let voice = app.state::<DiarizationState>().embed_segment(&segment.audio);
+            delivery.deliver(segment.timing, segment.continues, Delivery::Response(transcription, voice), dispatch_to(&app));
+            update_metrics(&app, |m| {
+                m.in_flight -= 1;
+                m.requests_completed += 1;
+            });
+            let _ = app.emit("god:status", "Listening...");
+        }
+        Err(ProviderError::InvalidResponse(e)) => {
+            // The request went through; only the engine's error streak cares
+            delivery.deliver(segment.timing, segment.continues, Delivery::Skipped, dispatch_to(&app));
+            report_processing_error(&app, record_invalid_response(&app.state::<ProcessingEngineState>(), e));
+            update_metrics(&app, |m| {
+                m.in_flight -= 1;
+                m.requests_completed += 1;
+            });
+        }
+        Err(e) => {
+            println!("[GEMINI] ✗ Error (#{}): {}", sequence, e);
+            delivery.deliver(segment.timing, segment.continues, Delivery::Skipped, dispatch_to(&app));
+            update_metrics(&app, |m| {
+                m.in_flight -= 1;
+                m.requests_failed += 1;
+            });
+            let _ = app.emit("god:status", format!("Error: {}. Waiting...", e));
             
-            (duration >= MIN_SPEECH_SECS && silence >= SILENCE_TIMEOUT_SECS)
-                || duration >= MAX_BATCH_SECS
-        } else { false };
+            // Emit error for frontend rotation
+            let code = if matches!(e, ProviderError::RateLimited(_)) { 429 } else { 500 };
+            let _ = app.emit("god:api_error", serde_json::json!({
+                "code": code,
+                "message": e.to_string()
+            }));
+
+            // Extra wait on error keeps this worker slot busy
+            sleep(Duration::from_secs(3)).await;
+            let _ = app.emit("god:status", "Listening...");
+        }
+    }
+}
+
+// ============================================================================
+// Live Streaming Mode
+// ============================================================================
+
+const LIVE_INPUT_CAPACITY: usize = 100; // ~10 s of ticks buffered across a reconnect
+const LIVE_VOICE_MAX_SAMPLES: usize = 30 * 16000; // Speech kept per turn for the speaker embedding
+
+fn is_live_model(model: &str) -> bool {
+    model.starts_with("gemini-live") || model.contains("-live-")
+}
+
+/// What a Live session shares with the batch path of the same capture session
+struct LiveSession {
+    clock: Instant,
+    wall_origin: u64,
+    sequence: Arc<AtomicU32>,         // One numbering for batch requests and Live turns
+    delivery: Arc<OrderedDelivery>,   // Turns wait behind batch results still in flight
+}
+
+/// Running Live session; dropping it closes the input and ends the session
+struct LiveHandle {
+    model: String,
+    key: String, // A new key restarts the session
+    input: mpsc::Sender<LiveInput>,
+    speaking: bool,
+    speech_start_ms: Arc<AtomicU64>, // Capture time of the current utterance's onset
+    voice: Arc<StdMutex<Vec<f32>>>,  // Speech since the last completed turn
+}
+
+impl LiveHandle {
+    fn start(app: AppHandle, key: &str, model: &str, session: LiveSession) -> Self {
+        println!("[LIVE] Starting streaming session with {}", model);
+        let (input, input_rx) = mpsc::channel(LIVE_INPUT_CAPACITY);
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let speech_start_ms = Arc::new(AtomicU64::new(0));
+        let voice = Arc::new(StdMutex::new(Vec::new()));
         
-        if should_process && !buffer.is_empty() {
-            let duration = buffer.len() as f32 / 16000.0;
-            
-            if duration >= MIN_SPEECH_SECS {
-                processing = true;
-                request_count += 1;
-                
-                let _ = app.emit("god:status", format!("Processing {:.1}s (#{})...", duration, request_count));
-                println!("[AUDIO] Processing {:.1}s, request #{}", duration, request_count);
-                
-                let audio = buffer.clone();
-                buffer.clear();
-                speaking = false;
-                speech_start = None;
-                last_speech = None;
-                
-                // Get current key and model from state
-                let (key, model) = {
-                    let state = app.state::<GeminiState>();
-                    let k: String = state.api_key.lock().unwrap().clone().unwrap_or_default();
-                    let m = state.selected_model.lock().unwrap().clone();
-                    (k, m)
-                };
+        tokio::spawn(run_live(LiveSetup::new(key, model, &app.state::<PromptState>().render()), input_rx, events_tx));
+        tokio::spawn(live_event_loop(app, events_rx, speech_start_ms.clone(), voice.clone(), session));
+        
+        Self { model: model.to_string(), key: key.to_string(), input, speaking: false, speech_start_ms, voice }
+    }
 
-                if key.is_empty() {
-                    println!("[GEMINI] ✗ Error: No API key configured");
-                    let _ = app.emit("god:status", "Error: No API key");
-                    let _ = app.emit("god:api_error", serde_json::json!({"code": 401, "message": "No API key configured"}));
-                    processing = false;
-                    continue;
-                }
-                
-                match call_gemini_with_backoff(&key, &model, &audio, &mut backoff, &mut last_request).await {
-                    Ok(response) => {
-                        println!("[GEMINI] ✓ Response received");
-                        let _ = app.emit("god:transcript", response);
-                        let _ = app.emit("god:status", "Listening...");
+    /// Streams speech (VAD hangover included) and marks where each utterance ends
+    fn push(&mut self, block: Vec<f32>, is_speech: bool, now_ms: u64) {
+        if is_speech {
+            if !self.speaking {
+                self.speaking = true;
+                self.speech_start_ms.store(now_ms, Ordering::Relaxed);
+            }
+            {
+                let mut voice = self.voice.lock().unwrap();
+                voice.extend_from_slice(&block);
+                let excess = voice.len().saturating_sub(LIVE_VOICE_MAX_SAMPLES);
+                voice.drain(..excess);
+            }
+            if !block.is_empty() && self.input.try_send(LiveInput::Audio(block)).is_err() {
+                println!("[LIVE] ✗ Input backlog full - dropping audio");
+            }
+        } else if self.speaking {
+            self.speaking = false;
+            let _ = self.input.try_send(LiveInput::EndOfSpeech);
+        }
+    }
+}
+
+/// Turns session events into UI events: partials become optimistic predictions,
+/// completed turns go through the same delivery and engine path as batch responses
+async fn live_event_loop(
+    app: AppHandle,
+    mut events: mpsc::UnboundedReceiver<LiveEvent>,
+    speech_start_ms: Arc<AtomicU64>,
+    voice: Arc<StdMutex<Vec<f32>>>,
+    session: LiveSession,
+) {
+    let mut turn_start: Option<u64> = None;
+    
+    while let Some(event) = events.recv().await {
+        match event {
+            LiveEvent::Connected => {
+                let _ = app.emit("god:status", "Live ✓ Streaming...");
+            }
+            LiveEvent::Partial(text) => {
+                turn_start.get_or_insert_with(|| speech_start_ms.load(Ordering::Relaxed));
+                let engine = app.state::<ProcessingEngineState>();
+                let prediction = generate_optimistic(&engine, &text);
+                let _ = app.emit("god:partial", serde_json::json!({
+                    "text": text,
+                    "prediction": prediction,
+                }));
+            }
+            LiveEvent::TurnComplete(raw) => {
+                let sequence = session.sequence.fetch_add(1, Ordering::Relaxed) + 1;
+                let end_ms = session.clock.elapsed().as_millis() as u64;
+                let start_ms = turn_start.take().unwrap_or(end_ms).min(end_ms);
+                let timing = SegmentTiming {
+                    sequence,
+                    start_ms,
+                    end_ms,
+                    wall_start_ms: session.wall_origin + start_ms,
+                    wall_end_ms: session.wall_origin + end_ms,
+                };
+                let audio = std::mem::take(&mut *voice.lock().unwrap());
+                let outcome = match parse_response(&raw) {
+                    Ok(transcription) => {
+                        let transcription = reanalyze_response(&app.state::<AnalysisState>(), transcription).await;
+                        let embedding = app.state::<DiarizationState>().embed_segment(&audio);
+                        Delivery::Response(transcription, embedding)
                     }
                     Err(e) => {
-                        println!("[GEMINI] ✗ Error: {}", e);
-                        let _ = app.emit("god:status", format!("Error: {}. Waiting...", e));
-                        
-                        // Emit error for frontend rotation
-                        let code = if e.contains("429") || e.contains("Rate limit") { 429 } else { 500 };
-                        let _ = app.emit("god:api_error", serde_json::json!({
-                            "code": code,
-                            "message": e
-                        }));
-
-                        // Extra wait on error
-                        sleep(Duration::from_secs(3)).await;
-                        let _ = app.emit("god:status", "Listening...");
+                        let engine = app.state::<ProcessingEngineState>();
+                        report_processing_error(&app, record_invalid_response(&engine, e.to_string()));
+                        Delivery::Skipped
                     }
-                }
-                
-                processing = false;
-            } else {
-                println!("[AUDIO] Discarding short segment ({:.1}s)", duration);
-                buffer.clear();
-                speaking = false;
-                speech_start = None;
-                last_speech = None;
+                };
+                session.delivery.deliver(timing, false, outcome, dispatch_to(&app));
+                update_metrics(&app, |m| m.requests_completed += 1);
+            }
+            LiveEvent::Reconnecting(reason) => {
+                turn_start = None;
+                voice.lock().unwrap().clear();
+                let _ = app.emit("god:status", format!("Reconnecting: {}", reason));
+            }
+        }
+    }
+}
+
+// ============================================================================
+// File Import - offline re-transcription of a recorded WAV
+// ============================================================================
+
+const IMPORT_BLOCK_SAMPLES: usize = 1600;       // Same 100 ms cadence as the live loop
+const IMPORT_MAX_ATTEMPTS: u32 = 3;             // Retries per segment when rate limited
+
+/// Decode any PCM/float WAV to 16 kHz mono
+pub fn read_wav_16k(path: &str) -> Result<Vec<f32>, String> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let spec = reader.spec();
+    
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = (1u64 << (spec.bits_per_sample.saturating_sub(1))) as f32;
+            reader.samples::<i32>().map(|s| s.map(|v| v as f32 / scale)).collect()
+        }
+    }.map_err(|e| format!("Failed to decode {}: {}", path, e))?;
+    
+    let channels = spec.channels.max(1) as usize;
+    let mono: Vec<f32> = interleaved.chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    
+    let mut resampler = StreamResampler::new(spec.sample_rate, TARGET_SAMPLE_RATE)?;
+    let mut out = resampler.process(&mono);
+    out.extend(resampler.flush());
+    
+    println!("[IMPORT] {}: {} Hz x{} -> {:.1}s at 16 kHz", 
+             path, spec.sample_rate, spec.channels, out.len() as f32 / 16000.0);
+    Ok(out)
+}
+
+struct Segment {
+    start_secs: f32,
+    audio: Vec<f32>,
+    continues: bool, // Overlaps the end of the previous segment
+}
+
+/// Cut a whole recording into speech segments with the live batching rules
+fn segment_recording(samples: &[f32], config: AudioBatchingConfig, vad_config: VadConfig) -> Vec<Segment> {
+    let mut batcher = SpeechBatcher::new(config);
+    let mut vad = Vad::new(vad_config);
+    let mut segments = Vec::new();
+    let mut position = 0usize;
+    
+    let blocks = samples.chunks(IMPORT_BLOCK_SAMPLES).map(Some).chain(std::iter::once(None));
+    for block in blocks {
+        let batch = match block {
+            Some(block) => {
+                position += block.len();
+                let is_speech = vad.process(block).is_speech;
+                batcher.push(position as f32 / 16000.0, block.to_vec(), is_speech)
+            }
+            None => batcher.finish(),
+        };
+        match batch {
+            Some(Batch::Ready { audio, continues }) => {
+                let start_secs = position.saturating_sub(audio.len()) as f32 / 16000.0;
+                segments.push(Segment { start_secs, audio, continues });
+            }
+            Some(Batch::Discarded(duration)) => {
+                println!("[IMPORT] Discarding short segment ({:.1}s)", duration);
             }
+            None => {}
+        }
+    }
+    segments
+}
+
+fn format_offset(secs: f32) -> String {
+    let total = secs.max(0.0) as u64;
+    if total >= 3600 {
+        format!("{:02}:{:02}:{:02}", total / 3600, (total / 60) % 60, total % 60)
+    } else {
+        format!("{:02}:{:02}", total / 60, total % 60)
+    }
+}
+
+/// Transcribe and analyse a recorded WAV file into a saved `SessionData`.
+/// Runs against an isolated processing engine so the live graph and cache are untouched.
+#[tauri::command]
+pub async fn transcribe_file(
+    state: tauri::State<'_, GeminiState>,
+    app: AppHandle,
+    path: String,
+    title: Option<String>,
+) -> Result<SessionData, String> {
+    let key = state.api_key.lock().unwrap().clone().unwrap_or_default();
+    let model = state.selected_model.lock().unwrap().clone();
+    if is_live_model(&model) {
+        return Err("File import needs a batch model; select a non-Live model first".into());
+    }
+    let provider = state.providers.resolve(&model)
+        .ok_or_else(|| format!("No provider serves model {}", model))?;
+    if key.is_empty() && provider.capabilities().requires_api_key {
+        return Err("No API key configured".into());
+    }
+    
+    let source = path.clone();
+    let samples = tokio::task::spawn_blocking(move || read_wav_16k(&source))
+        .await
+        .map_err(|e| e.to_string())??;
+    let config = state.batching.lock().unwrap().capped_to(provider.capabilities().max_segment_secs);
+    let vad_config = *app.state::<AudioState>().vad_config.lock().unwrap();
+    let segments = tokio::task::spawn_blocking({
+        let samples = samples.clone();
+        move || segment_recording(&samples, config, vad_config)
+    }).await.map_err(|e| e.to_string())?;
+    
+    let total = segments.len();
+    println!("[IMPORT] {} speech segments to analyse", total);
+    
+    let engine = ProcessingEngineState {
+        settings: StdMutex::new(app.state::<ProcessingEngineState>().settings.lock().unwrap().clone()),
+        ..Default::default()
+    };
+    
+    let name = std::path::Path::new(&path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "recording".to_string());
+    let mut session = SessionData::new(title.unwrap_or_else(|| format!("Imported: {}", name)));
+    session.metadata.duration_seconds = (samples.len() / 16000) as u64;
+    session.metadata.tags.push("imported".to_string());
+    let prompt = app.state::<PromptState>().selected_ref();
+    let system_prompt = prompt.rendered.clone();
+    session.prompt_template = Some(prompt);
+    
+    let limiter = RateLimiter::default();
+    let mut failed = 0usize;
+    let mut last_transcript: Option<String> = None;
+    let mut conversation = ConversationContext::default();
+    let diarization = app.state::<DiarizationState>();
+    let diarization_config = *diarization.config.lock().unwrap();
+    let mut speakers = SpeakerClusters::default();
+    
+    for (index, segment) in segments.iter().enumerate() {
+        // Taken up front so a failed or silent segment doesn't leave a stale one behind
+        let previous = last_transcript.take().filter(|_| segment.continues);
+        let _ = app.emit("god:import_progress", serde_json::json!({
+            "current": index + 1,
+            "total": total,
+            "path": path,
+        }));
+        let _ = app.emit("god:status", format!("Importing {}/{}...", index + 1, total));
+        
+        let context = conversation.render(config.context_entries, config.context_token_budget);
+        let mut response = Err(ProviderError::Failed(String::new()));
+        for _ in 0..IMPORT_MAX_ATTEMPTS {
+            let request = TranscriptionRequest {
+                api_key: &key,
+                model: &model,
+                audio: &segment.audio,
+                source_note: "",
+                system_prompt: &system_prompt,
+                context: &context,
+            };
+            response = transcribe_with_limits(provider.as_ref(), request, &config, &limiter).await;
+            if limiter.backoff() == 0 { break; } // Only rate limiting is worth retrying
         }
         
-        // Prevent buffer from growing too large
-        let max_samples = (MAX_BATCH_SECS * 16000.0) as usize;
-        if buffer.len() > max_samples {
-            buffer.drain(0..buffer.len() - max_samples);
+        let transcription = match response {
+            Ok(transcription) => reanalyze_response(&app.state::<AnalysisState>(), transcription).await,
+            Err(e) => {
+                println!("[IMPORT] ✗ Segment {} at {}: {}", index + 1, format_offset(segment.start_secs), e);
+                failed += 1;
+                continue;
+            }
+        };
+        if transcription.is_silence() { continue; }
+        
+        match process_intelligence(&engine, transcription, previous.as_deref()) {
+            Ok(Some(mut output)) => {
+                if diarization_config.enabled {
+                    let secs = segment.audio.len() as f32 / 16000.0;
+                    if let Some(id) = diarization.embedder.embed(&segment.audio)
+                        .and_then(|v| speakers.assign(&v, secs, &diarization_config).map(|s| s.id.clone()))
+                    {
+                        let voiceprints = diarization.voiceprints.lock().unwrap();
+                        if let Some((from, to)) = speakers.identify(&id, &voiceprints, diarization_config.voiceprint_threshold) {
+                            session.rename_speaker(&from, &to);
+                            conversation.rename(&from, &to);
+                        }
+                        let label = speakers.speakers().iter().find(|s| s.id == id).map(|s| s.label().to_string());
+                        output.speaker_id = label.unwrap_or(id);
+                    }
+                }
+                last_transcript = Some(output.transcript_chunk.clone());
+                conversation.record(&output.speaker_id, &output.transcript_chunk);
+                let start_ms = (segment.start_secs * 1000.0) as u64;
+                session.add_transcript(TranscriptEntry {
+                    timestamp: format_offset(segment.start_secs),
+                    speaker_id: output.speaker_id,
+                    text: output.transcript_chunk,
+                    tone: output.intelligence.tone,
+                    category: Some(output.intelligence.category),
+                    confidence: output.intelligence.confidence,
+                    start_ms: Some(start_ms),
+                    end_ms: Some(start_ms + segment.audio.len() as u64 / 16),
+                });
+            }
+            Ok(None) => {}
+            Err(e) => {
+                println!("[IMPORT] ✗ Segment {} rejected: {}", index + 1, e);
+                failed += 1;
+            }
         }
     }
+    
+    let (nodes, edges) = engine.graph.get_graph_data();
+    for node in nodes {
+        session.add_graph_node(SessionGraphNode {
+            id: node.id,
+            node_type: node.node_type,
+            metadata: HashMap::from([("label".to_string(), node.label)]),
+        });
+    }
+    for edge in edges {
+        session.add_graph_edge(SessionGraphEdge {
+            from: edge.from,
+            to: edge.to,
+            relation: edge.relation,
+            weight: edge.weight,
+        });
+    }
+    SessionManager::new()?.save_session(&session)?;
+    println!("[IMPORT] ✓ {} transcripts from {} segments ({} failed)", 
+             session.transcripts.len(), total, failed);
+    let _ = app.emit("god:status", format!("Imported {} transcripts", session.transcripts.len()));
+    Ok(session)
+}
+
+#[tauri::command]
+pub fn get_batching_config(state: tauri::State<'_, GeminiState>) -> Result<AudioBatchingConfig, String> {
+    let config = state.batching.lock().map_err(|e| e.to_string())?;
+    Ok(*config)
+}
+
+/// Validates, persists and hands the config to the running loop (applied on its next tick)
+#[tauri::command]
+pub fn update_batching_config(
+    state: tauri::State<'_, GeminiState>,
+    config: AudioBatchingConfig,
+) -> Result<AudioBatchingConfig, String> {
+    config.validate()?;
+    let mut current = state.batching.lock().map_err(|e| e.to_string())?;
+    config.save()?;
+    *current = config;
+    println!("[GEMINI] Batching config: {:?}", config);
+    Ok(config)
 }
 
+/// Forget the previous session's transcript and speakers; called when recording starts
+#[tauri::command]
+pub fn reset_conversation_context(
+    state: tauri::State<'_, GeminiState>,
+    diarization: tauri::State<'_, DiarizationState>,
+) -> Result<(), String> {
+    state.context.lock().map_err(|e| e.to_string())?.clear();
+    diarization.speakers.lock().map_err(|e| e.to_string())?.clear();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_pipeline_metrics(state: tauri::State<'_, GeminiState>) -> Result<PipelineMetrics, String> {
+    let metrics = state.metrics.lock().map_err(|e| e.to_string())?;
+    Ok(metrics.clone())
+}
+
+/// Selecting a model also selects its provider; the running loop switches on the next segment
 #[tauri::command]
 pub fn set_gemini_model(state: tauri::State<'_, GeminiState>, model: String) -> Result<String, String> {
+    let provider = state.providers.resolve(&model)
+        .ok_or_else(|| format!("Unknown model: {}", model))?;
     *state.selected_model.lock().unwrap() = model.clone();
-    Ok(format!("Model: {}", model))
+    Ok(format!("Model: {} ({})", model, provider.id()))
 }
 
 #[tauri::command]
-pub fn get_available_models() -> Vec<serde_json::Value> {
-    vec![
-        serde_json::json!({"id": "gemini-2.5-flash-preview-09-2025", "name": "⚡ Gemini 2.5 Flash"}),
-        serde_json::json!({"id": "gemini-2.5-flash-lite-preview-09-2025", "name": "🔥 Gemini 2.5 Flash Lite"}),
-        serde_json::json!({"id": "gemini-3-flash-preview", "name": "💎 Gemini 3 Flash"}),
-    ]
+pub fn get_available_models(state: tauri::State<'_, GeminiState>) -> Vec<serde_json::Value> {
+    state.providers.models()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: f32 = 0.1;
+
+    fn timing(sequence: u32) -> SegmentTiming {
+        SegmentTiming { sequence, start_ms: 0, end_ms: 0, wall_start_ms: 0, wall_end_ms: 0 }
+    }
+
+    /// (sequence, text, previous transcript) for each response the sink received
+    type Log = Vec<(u32, String, Option<String>)>;
+
+    fn deliver(delivery: &OrderedDelivery, log: &mut Log, sequence: u32, continues: bool, outcome: Delivery) {
+        delivery.deliver(timing(sequence), continues, outcome, |transcription, previous, timing, _| {
+            let emitted = transcription.text.to_uppercase();
+            log.push((timing.sequence, transcription.text, previous.map(String::from)));
+            Some(emitted)
+        });
+    }
+
+    fn response(text: &str) -> Delivery {
+        Delivery::Response(Transcription { text: text.to_string(), ..Default::default() }, None)
+    }
+
+    fn speech(secs: f32) -> Vec<f32> {
+        vec![0.1; (secs * 16000.0) as usize]
+    }
+
+    #[test]
+    fn batcher_ends_segment_when_audio_stops_arriving() {
+        let config = AudioBatchingConfig::default();
+        let mut batcher = SpeechBatcher::new(config);
+        let mut now = 0.0;
+        for _ in 0..30 {
+            now += TICK;
+            assert!(batcher.push(now, speech(TICK), true).is_none());
+        }
+
+        // Mixer stops sending after its silence skip: only empty ticks from here on
+        let mut ended_at = None;
+        for _ in 0..50 {
+            now += TICK;
+            if let Some(batch) = batcher.push(now, Vec::new(), false) {
+                ended_at = Some((now, batch));
+                break;
+            }
+        }
+        let (at, batch) = ended_at.expect("pause never ended the segment");
+        assert!(at - 3.0 >= config.silence_timeout_secs - 0.01);
+        assert!(at - 3.0 < config.silence_timeout_secs + TICK + 0.01);
+        match batch {
+            Batch::Ready { audio, continues } => {
+                assert_eq!(audio.len(), 30 * 1600);
+                assert!(!continues);
+            }
+            Batch::Discarded(_) => panic!("segment was long enough to send"),
+        }
+        assert!(batcher.push(now + TICK, Vec::new(), false).is_none());
+    }
+
+    #[test]
+    fn delivery_releases_in_capture_order() {
+        let delivery = OrderedDelivery::new(1);
+        let mut log = Vec::new();
+        deliver(&delivery, &mut log, 3, false, response("c"));
+        deliver(&delivery, &mut log, 2, false, response("b"));
+        assert!(log.is_empty());
+
+        deliver(&delivery, &mut log, 1, false, response("a"));
+        let order: Vec<u32> = log.iter().map(|(seq, ..)| *seq).collect();
+        assert_eq!(order, [1, 2, 3]);
+    }
+
+    #[test]
+    fn delivery_skips_holes_without_stalling() {
+        let delivery = OrderedDelivery::new(1);
+        let mut log = Vec::new();
+        deliver(&delivery, &mut log, 3, true, response("c"));
+        deliver(&delivery, &mut log, 1, false, response("a"));
+        assert_eq!(log.len(), 1);
+
+        deliver(&delivery, &mut log, 2, false, Delivery::Skipped);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1].0, 3);
+        assert_eq!(log[1].2, None, "a skipped segment leaves nothing to de-duplicate against");
+
+        deliver(&delivery, &mut log, 4, false, response("d"));
+        assert_eq!(log.last().unwrap().0, 4);
+    }
+
+    #[test]
+    fn delivery_carries_previous_transcript_only_into_overlaps() {
+        let delivery = OrderedDelivery::new(1);
+        let mut log = Vec::new();
+        deliver(&delivery, &mut log, 2, true, response("b"));
+        deliver(&delivery, &mut log, 3, false, response("c"));
+        deliver(&delivery, &mut log, 1, false, response("a"));
+
+        assert_eq!(log[0], (1, "a".to_string(), None));
+        assert_eq!(log[1], (2, "b".to_string(), Some("A".to_string())));
+        assert_eq!(log[2], (3, "c".to_string(), None));
+    }
+
+    #[test]
+    fn delivery_keeps_the_first_result_for_a_sequence() {
+        let delivery = OrderedDelivery::new(1);
+        let mut log = Vec::new();
+        deliver(&delivery, &mut log, 2, false, response("b"));
+        deliver(&delivery, &mut log, 2, false, Delivery::Skipped);
+        deliver(&delivery, &mut log, 1, false, response("a"));
+        assert_eq!(log.iter().map(|(_, text, _)| text.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+
+        deliver(&delivery, &mut log, 1, false, Delivery::Skipped);
+        deliver(&delivery, &mut log, 3, false, response("c"));
+        assert_eq!(log.last().unwrap().0, 3, "a late duplicate must not hold anything back");
+    }
+
+    #[tokio::test]
+    async fn panicked_upload_is_skipped_so_later_results_are_released() {
+        let delivery = OrderedDelivery::new(1);
+        let mut log = Vec::new();
+        let mut uploads = HashMap::new();
+        let mut workers: JoinSet<()> = JoinSet::new();
+        let handle = workers.spawn(async { panic!("upload task panicked") });
+        uploads.insert(handle.id(), (timing(1), false));
+
+        deliver(&delivery, &mut log, 2, false, response("b"));
+        assert!(log.is_empty());
+
+        let joined = workers.join_next_with_id().await.unwrap();
+        let (skipped, continues) = panicked_upload(&mut uploads, joined).expect("panic went unnoticed");
+        deliver(&delivery, &mut log, skipped.sequence, continues, Delivery::Skipped);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].0, 2);
+        assert!(uploads.is_empty());
+    }
+
+    #[test]
+    fn gemini_response_parses_to_typed_transcription() {
+        let raw = r#"{"speaker_id":"Speaker 2","transcript_chunk":"ship it","is_final":true,"intelligence":{"category":["DECISION"],"tone":"NEUTRAL","confidence":0.9}}"#;
+        let transcription = parse_response(raw).unwrap();
+        assert_eq!(transcription.text, "ship it");
+        assert_eq!(transcription.speaker_id.as_deref(), Some("Speaker 2"));
+        assert_eq!(transcription.intelligence.unwrap().category, vec!["DECISION"]);
+
+        assert!(parse_response(r#"{"status":"silence"}"#).unwrap().is_silence());
+        assert!(parse_response(r#"{"speaker_id":"Speaker 1","transcript_chunk":" "}"#).unwrap().is_silence());
+        assert!(matches!(parse_response("Sorry, I can't help with that."), Err(ProviderError::InvalidResponse(_))));
+    }
 }
@@ -0,0 +1,320 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::gemini_client::pcm16;
+
+// ============================================================================
+// GEMINI LIVE - bidirectional WebSocket streaming session
+// ============================================================================
+
+pub const LIVE_URL: &str =
+    "wss://generativelanguage.googleapis.com/ws/google.ai.generativelanguage.v1beta.GenerativeService.BidiGenerateContent";
+
+const SETUP_TIMEOUT_SECS: u64 = 10;
+const INITIAL_RECONNECT_MS: u64 = 500;
+const MAX_RECONNECT_MS: u64 = 30_000;
+
+/// Everything needed to (re)open a session
+#[derive(Debug, Clone)]
+pub struct LiveSetup {
+    pub url: String, // Full WebSocket URL including the key query parameter
+    pub model: String,
+    pub system_prompt: String,
+}
+
+impl LiveSetup {
+    pub fn new(api_key: &str, model: &str, system_prompt: &str) -> Self {
+        Self {
+            url: format!("{}?key={}", LIVE_URL, api_key),
+            model: model.to_string(),
+            system_prompt: system_prompt.to_string(),
+        }
+    }
+
+    fn message(&self) -> Value {
+        json!({
+            "setup": {
+                "model": format!("models/{}", self.model.trim_start_matches("models/")),
+                "generationConfig": {
+                    "responseModalities": ["TEXT"],
+                    "temperature": 0.1,
+                },
+                "systemInstruction": { "parts": [{ "text": self.system_prompt }] },
+                "inputAudioTranscription": {},
+            }
+        })
+    }
+}
+
+/// Audio side of the session, fed by the capture loop
+#[derive(Debug, Clone)]
+pub enum LiveInput {
+    Audio(Vec<f32>), // 16 kHz mono
+    EndOfSpeech,     // Lets the server close the turn without waiting for its own VAD
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiveEvent {
+    Connected,
+    Partial(String),       // Input transcription so far for the current turn
+    TurnComplete(String),  // Model text for the finished turn (GOD schema JSON)
+    Reconnecting(String),  // Why the previous connection ended
+}
+
+// ============================================================================
+// SESSION
+// ============================================================================
+
+/// Runs sessions until `input` is closed, reconnecting with exponential backoff.
+/// Audio sent while disconnected waits in the channel and is flushed on reconnect.
+pub async fn run_live(setup: LiveSetup, mut input: mpsc::Receiver<LiveInput>, events: mpsc::UnboundedSender<LiveEvent>) {
+    let mut backoff_ms = INITIAL_RECONNECT_MS;
+    loop {
+        let mut connected = false;
+        match run_session(&setup, &mut input, &events, &mut connected).await {
+            Ok(()) => {
+                println!("[LIVE] Input closed, session ended");
+                return;
+            }
+            Err(reason) => {
+                if connected { backoff_ms = INITIAL_RECONNECT_MS; }
+                println!("[LIVE] ✗ {} - reconnecting in {}ms", reason, backoff_ms);
+                if events.send(LiveEvent::Reconnecting(reason)).is_err() { return; }
+                sleep(Duration::from_millis(backoff_ms)).await;
+                backoff_ms = (backoff_ms * 2).min(MAX_RECONNECT_MS);
+            }
+        }
+    }
+}
+
+/// Turn text and transcription accumulated until the server marks the turn complete
+#[derive(Default)]
+struct Turn {
+    transcript: String,
+    model_text: String,
+}
+
+/// One connection. Ok = input closed (normal shutdown), Err = reconnect.
+async fn run_session(
+    setup: &LiveSetup,
+    input: &mut mpsc::Receiver<LiveInput>,
+    events: &mpsc::UnboundedSender<LiveEvent>,
+    connected: &mut bool,
+) -> Result<(), String> {
+    let (ws, _) = connect_async(setup.url.as_str()).await
+        .map_err(|e| format!("Connect: {}", e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    sink.send(Message::Text(setup.message().to_string())).await
+        .map_err(|e| format!("Setup send: {}", e))?;
+
+    // Nothing may be streamed before the server acknowledges the setup
+    timeout(Duration::from_secs(SETUP_TIMEOUT_SECS), async {
+        while let Some(msg) = stream.next().await {
+            let msg = msg.map_err(|e| format!("Setup: {}", e))?;
+            if let Some(value) = parse_message(&msg) {
+                if value.get("setupComplete").is_some() { return Ok(()); }
+            } else if msg.is_close() {
+                return Err(format!("Closed during setup: {:?}", msg));
+            }
+        }
+        Err("Closed during setup".to_string())
+    }).await.map_err(|_| "Setup timed out".to_string())??;
+
+    *connected = true;
+    println!("[LIVE] Session ready ({})", setup.model);
+    let _ = events.send(LiveEvent::Connected);
+
+    let mut turn = Turn::default();
+    loop {
+        tokio::select! {
+            msg = stream.next() => {
+                let msg = match msg {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(e)) => return Err(format!("Socket: {}", e)),
+                    None => return Err("Socket closed".into()),
+                };
+                if msg.is_close() {
+                    return Err(format!("Server closed: {:?}", msg));
+                }
+                if let Some(value) = parse_message(&msg) {
+                    handle_server_message(&value, &mut turn, events)?;
+                }
+            }
+            next = input.recv() => {
+                let Some(next) = next else {
+                    let _ = sink.send(Message::Close(None)).await;
+                    return Ok(());
+                };
+                sink.send(Message::Text(input_message(&next).to_string())).await
+                    .map_err(|e| format!("Send: {}", e))?;
+            }
+        }
+    }
+}
+
+/// The server sends JSON in either text or binary frames
+fn parse_message(msg: &Message) -> Option<Value> {
+    match msg {
+        Message::Text(text) => serde_json::from_str(text).ok(),
+        Message::Binary(bytes) => serde_json::from_slice(bytes).ok(),
+        _ => None,
+    }
+}
+
+fn input_message(input: &LiveInput) -> Value {
+    match input {
+        LiveInput::Audio(samples) => {
+            let pcm: Vec<u8> = samples.iter().flat_map(|s| pcm16(*s)).collect();
+            json!({
+                "realtimeInput": {
+                    "audio": { "data": BASE64.encode(pcm), "mimeType": "audio/pcm;rate=16000" }
+                }
+            })
+        }
+        LiveInput::EndOfSpeech => json!({ "realtimeInput": { "audioStreamEnd": true } }),
+    }
+}
+
+fn handle_server_message(
+    value: &Value,
+    turn: &mut Turn,
+    events: &mpsc::UnboundedSender<LiveEvent>,
+) -> Result<(), String> {
+    if let Some(go_away) = value.get("goAway") {
+        return Err(format!("Server going away ({})", go_away.get("timeLeft").and_then(Value::as_str).unwrap_or("now")));
+    }
+    let Some(content) = value.get("serverContent") else { return Ok(()) };
+
+    if let Some(text) = content.pointer("/inputTranscription/text").and_then(Value::as_str) {
+        turn.transcript.push_str(text);
+        let _ = events.send(LiveEvent::Partial(turn.transcript.trim().to_string()));
+    }
+    if let Some(parts) = content.pointer("/modelTurn/parts").and_then(Value::as_array) {
+        for text in parts.iter().filter_map(|p| p.get("text").and_then(Value::as_str)) {
+            turn.model_text.push_str(text);
+        }
+    }
+    let interrupted = content.get("interrupted").and_then(Value::as_bool).unwrap_or(false);
+    let complete = content.get("turnComplete").and_then(Value::as_bool).unwrap_or(false);
+    if complete || interrupted {
+        let finished = std::mem::take(turn);
+        if complete && !finished.model_text.trim().is_empty() {
+            let _ = events.send(LiveEvent::TurnComplete(finished.model_text.trim().to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    fn mock_setup(port: u16) -> LiveSetup {
+        LiveSetup {
+            url: format!("ws://127.0.0.1:{}", port),
+            model: "mock-live".to_string(),
+            system_prompt: "prompt".to_string(),
+        }
+    }
+
+    async fn next_json<S>(ws: &mut S) -> Value
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        loop {
+            let msg = ws.next().await.unwrap().unwrap();
+            if let Some(value) = parse_message(&msg) { return value; }
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_audio_and_reports_partials_and_turns() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(tcp).await.unwrap();
+
+            let setup = next_json(&mut ws).await;
+            assert_eq!(setup.pointer("/setup/model").unwrap(), "models/mock-live");
+            ws.send(Message::Binary(br#"{"setupComplete":{}}"#.to_vec())).await.unwrap();
+
+            let audio = next_json(&mut ws).await;
+            let data = audio.pointer("/realtimeInput/audio/data").unwrap().as_str().unwrap();
+            assert_eq!(BASE64.decode(data).unwrap().len(), 320); // 160 samples, 16-bit
+            let end = next_json(&mut ws).await;
+            assert_eq!(end.pointer("/realtimeInput/audioStreamEnd").unwrap(), true);
+
+            for msg in [
+                r#"{"serverContent":{"inputTranscription":{"text":"hello "}}}"#,
+                r#"{"serverContent":{"inputTranscription":{"text":"world"}}}"#,
+                r#"{"serverContent":{"modelTurn":{"parts":[{"text":"{\"a\":"}]}}}"#,
+                r#"{"serverContent":{"modelTurn":{"parts":[{"text":"1}"}]},"turnComplete":true}}"#,
+            ] {
+                ws.send(Message::Text(msg.to_string())).await.unwrap();
+            }
+            // Drain until the client hangs up
+            while let Some(Ok(msg)) = ws.next().await {
+                if msg.is_close() { break; }
+            }
+        });
+
+        let (input_tx, input_rx) = mpsc::channel(8);
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        let client = tokio::spawn(run_live(mock_setup(port), input_rx, events_tx));
+
+        assert_eq!(events.recv().await.unwrap(), LiveEvent::Connected);
+        input_tx.send(LiveInput::Audio(vec![0.1; 160])).await.unwrap();
+        input_tx.send(LiveInput::EndOfSpeech).await.unwrap();
+
+        assert_eq!(events.recv().await.unwrap(), LiveEvent::Partial("hello".into()));
+        assert_eq!(events.recv().await.unwrap(), LiveEvent::Partial("hello world".into()));
+        assert_eq!(events.recv().await.unwrap(), LiveEvent::TurnComplete(r#"{"a":1}"#.into()));
+
+        drop(input_tx);
+        timeout(Duration::from_secs(5), client).await.unwrap().unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_server_drops() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            // First connection: complete setup, then announce shutdown
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(tcp).await.unwrap();
+            next_json(&mut ws).await;
+            ws.send(Message::Text(r#"{"setupComplete":{}}"#.into())).await.unwrap();
+            ws.send(Message::Text(r#"{"goAway":{"timeLeft":"0s"}}"#.into())).await.unwrap();
+
+            // Second connection receives the audio queued meanwhile
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(tcp).await.unwrap();
+            next_json(&mut ws).await;
+            ws.send(Message::Text(r#"{"setupComplete":{}}"#.into())).await.unwrap();
+            let audio = next_json(&mut ws).await;
+            assert!(audio.pointer("/realtimeInput/audio").is_some());
+        });
+
+        let (input_tx, input_rx) = mpsc::channel(8);
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        tokio::spawn(run_live(mock_setup(port), input_rx, events_tx));
+
+        assert_eq!(events.recv().await.unwrap(), LiveEvent::Connected);
+        assert!(matches!(events.recv().await.unwrap(), LiveEvent::Reconnecting(_)));
+        input_tx.send(LiveInput::Audio(vec![0.0; 160])).await.unwrap();
+        assert_eq!(events.recv().await.unwrap(), LiveEvent::Connected);
+
+        timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,321 @@
+use serde_json::{json, Map, Value};
+
+// ============================================================================
+// INTELLIGENCE SCHEMA - single source for the prompt format, Gemini's
+// responseSchema and response validation
+// ============================================================================
+
+/// Bump whenever a field or enum value changes; printed in the prompt
+pub const SCHEMA_VERSION: u32 = 10;
+
+pub struct Choice {
+    pub value: &'static str,
+    pub meaning: &'static str,
+}
+
+pub enum Kind {
+    Integer,
+    Number,
+    Boolean,
+    Text,
+    Enum(&'static [Choice]),
+    List(&'static Kind),
+    Object(&'static [Field]),
+}
+
+pub struct Field {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub required: bool,
+    pub example: &'static str, // JSON literal for the prompt example, "" = left out; objects build their own
+    pub note: &'static str,    // Prompt rule and Gemini description
+}
+
+pub const CATEGORIES: &[Choice] = &[
+    Choice { value: "TASK", meaning: "work someone has to do" },
+    Choice { value: "DECISION", meaning: "something agreed or settled" },
+    Choice { value: "DEADLINE", meaning: "a date or time something is due" },
+    Choice { value: "QUERY", meaning: "an open question" },
+    Choice { value: "ACTION_ITEM", meaning: "a task with a named owner" },
+    Choice { value: "RISK", meaning: "a problem, blocker or concern" },
+    Choice { value: "SENTIMENT", meaning: "a clear opinion or feeling" },
+    Choice { value: "URGENCY", meaning: "pressure to act now" },
+    Choice { value: "INTERRUPTION", meaning: "a speaker cuts someone off" },
+    Choice { value: "AGREEMENT", meaning: "explicit agreement" },
+    Choice { value: "DISAGREEMENT", meaning: "explicit disagreement" },
+    Choice { value: "OFF_TOPIC", meaning: "unrelated to the meeting" },
+    Choice { value: "EMOTION_SHIFT", meaning: "the mood changes" },
+    Choice { value: "DOMINANCE_SHIFT", meaning: "someone else takes control of the discussion" },
+    Choice { value: "EMPATHY_GAP", meaning: "a concern is dismissed or ignored" },
+    Choice { value: "TOPIC_DRIFT", meaning: "the discussion wanders from the agenda" },
+];
+
+pub const TONES: &[Choice] = &[
+    Choice { value: "NEUTRAL", meaning: "matter-of-fact" },
+    Choice { value: "URGENT", meaning: "pressing, time-critical" },
+    Choice { value: "FRUSTRATED", meaning: "annoyed or stuck" },
+    Choice { value: "EXCITED", meaning: "energetic, enthusiastic" },
+    Choice { value: "POSITIVE", meaning: "pleased, supportive" },
+    Choice { value: "NEGATIVE", meaning: "unhappy, critical" },
+    Choice { value: "HESITANT", meaning: "unsure, holding back" },
+    Choice { value: "DOMINANT", meaning: "forceful, talking over others" },
+    Choice { value: "EMPATHETIC", meaning: "understanding, supportive of others" },
+];
+
+const ENTITY_FIELDS: &[Field] = &[
+    Field { name: "text", kind: Kind::Text, required: true, example: "", note: "" },
+    Field { name: "type", kind: Kind::Text, required: true, example: "", note: "PERSON, ORG, DATE or TOPIC" },
+];
+
+const GRAPH_UPDATE_FIELDS: &[Field] = &[
+    Field { name: "node_a", kind: Kind::Text, required: true, example: "", note: "" },
+    Field { name: "relation", kind: Kind::Text, required: true, example: "", note: "" },
+    Field { name: "node_b", kind: Kind::Text, required: true, example: "", note: "" },
+    Field { name: "weight", kind: Kind::Number, required: false, example: "", note: "" },
+];
+
+pub const INTELLIGENCE_FIELDS: &[Field] = &[
+    Field {
+        name: "category",
+        kind: Kind::List(&Kind::Enum(CATEGORIES)),
+        required: true,
+        example: r#"["TASK"]"#,
+        note: "zero or more; [] for plain information",
+    },
+    Field { name: "summary", kind: Kind::Text, required: false, example: r#""short gist""#, note: "" },
+    Field { name: "tone", kind: Kind::Enum(TONES), required: true, example: r#""NEUTRAL""#, note: "" },
+    Field { name: "confidence", kind: Kind::Number, required: true, example: "0.85", note: "0-1" },
+    Field {
+        name: "entities",
+        kind: Kind::List(&Kind::Object(ENTITY_FIELDS)),
+        required: false,
+        example: r#"[{"text":"Ali","type":"PERSON"}]"#,
+        note: "people, organisations, dates and topics mentioned, or []",
+    },
+    Field {
+        name: "graph_updates",
+        kind: Kind::List(&Kind::Object(GRAPH_UPDATE_FIELDS)),
+        required: false,
+        example: r#"[{"node_a":"Ali","relation":"owns","node_b":"Report"}]"#,
+        note: "only explicit relations between people, tasks and topics, or []",
+    },
+];
+
+/// Mirrors `IntelligenceOutput`
+pub const OUTPUT_FIELDS: &[Field] = &[
+    Field { name: "timestamp_ms", kind: Kind::Integer, required: true, example: "0", note: "" },
+    Field { name: "speaker_id", kind: Kind::Text, required: true, example: r#""Speaker 1""#, note: "" },
+    Field {
+        name: "transcript_chunk",
+        kind: Kind::Text,
+        required: true,
+        example: r#""exact text""#,
+        note: "exact words spoken; empty for silence",
+    },
+    Field { name: "is_final", kind: Kind::Boolean, required: true, example: "true", note: "" },
+    Field {
+        name: "intelligence",
+        kind: Kind::Object(INTELLIGENCE_FIELDS),
+        required: true,
+        example: "",
+        note: "",
+    },
+];
+
+pub fn category_values() -> impl Iterator<Item = &'static str> {
+    CATEGORIES.iter().map(|c| c.value)
+}
+
+pub fn is_category(value: &str) -> bool {
+    CATEGORIES.iter().any(|c| c.value == value)
+}
+
+pub fn is_tone(value: &str) -> bool {
+    TONES.iter().any(|t| t.value == value)
+}
+
+// ============================================================================
+// GENERATORS
+// ============================================================================
+
+fn gemini_type(kind: &Kind) -> Value {
+    match kind {
+        Kind::Integer => json!({ "type": "INTEGER" }),
+        Kind::Number => json!({ "type": "NUMBER" }),
+        Kind::Boolean => json!({ "type": "BOOLEAN" }),
+        Kind::Text => json!({ "type": "STRING" }),
+        Kind::Enum(choices) => json!({
+            "type": "STRING",
+            "enum": choices.iter().map(|c| c.value).collect::<Vec<_>>(),
+        }),
+        Kind::List(item) => json!({ "type": "ARRAY", "items": gemini_type(item) }),
+        Kind::Object(fields) => {
+            let mut properties = Map::new();
+            for field in fields.iter() {
+                let mut property = gemini_type(&field.kind);
+                if !field.note.is_empty() {
+                    property["description"] = json!(field.note);
+                }
+                properties.insert(field.name.to_string(), property);
+            }
+            let required: Vec<&str> = fields.iter().filter(|f| f.required).map(|f| f.name).collect();
+            json!({ "type": "OBJECT", "properties": properties, "required": required })
+        }
+    }
+}
+
+/// `responseSchema` for Gemini structured output
+pub fn response_schema() -> Value {
+    gemini_type(&Kind::Object(OUTPUT_FIELDS))
+}
+
+fn example_object(fields: &[Field]) -> String {
+    let members: Vec<String> = fields.iter()
+        .filter_map(|field| {
+            let value = match &field.kind {
+                Kind::Object(children) => example_object(children),
+                _ if field.example.is_empty() => return None,
+                _ => field.example.to_string(),
+            };
+            Some(format!("\"{}\":{}", field.name, value))
+        })
+        .collect();
+    format!("{{{}}}", members.join(","))
+}
+
+fn rule_lines(fields: &[Field], prefix: &str, lines: &mut Vec<String>) {
+    for field in fields {
+        let path = format!("{}{}", prefix, field.name);
+        let choices = match &field.kind {
+            Kind::Enum(choices) => Some(("one of", *choices)),
+            Kind::List(Kind::Enum(choices)) => Some(("any of", *choices)),
+            _ => None,
+        };
+        match (choices, field.note.is_empty()) {
+            (Some((how, choices)), _) => {
+                let note = if field.note.is_empty() { String::new() } else { format!(" ({})", field.note) };
+                lines.push(format!("- {}: {}{}", path, how, note));
+                lines.extend(choices.iter().map(|c| format!("    {} = {}", c.value, c.meaning)));
+            }
+            (None, false) => lines.push(format!("- {}: {}", path, field.note)),
+            (None, true) => {}
+        }
+        match &field.kind {
+            Kind::Object(children) => rule_lines(children, &format!("{}.", path), lines),
+            Kind::List(Kind::Object(children)) => rule_lines(children, &format!("{}[].", path), lines),
+            _ => {}
+        }
+    }
+}
+
+/// Prompt section describing the expected JSON, generated from `fields`
+pub fn prompt_format(fields: &[Field]) -> String {
+    let mut lines = Vec::new();
+    rule_lines(fields, "", &mut lines);
+    format!(
+        "OUTPUT FORMAT (schema v{}) - JSON ONLY, no markdown:\n{}\n\nFIELDS:\n{}",
+        SCHEMA_VERSION,
+        example_object(fields),
+        lines.join("\n")
+    )
+}
+
+// ============================================================================
+// VALIDATION
+// ============================================================================
+
+fn check(kind: &Kind, value: &Value, path: &str) -> Result<(), String> {
+    let ok = match kind {
+        Kind::Integer => value.is_u64(),
+        Kind::Number => value.is_number(),
+        Kind::Boolean => value.is_boolean(),
+        Kind::Text => value.is_string(),
+        Kind::Enum(choices) => {
+            let Some(text) = value.as_str() else { return Err(format!("{}: expected a string", path)) };
+            if !choices.iter().any(|c| c.value == text) {
+                return Err(format!("{}: {:?} is not a valid value", path, text));
+            }
+            true
+        }
+        Kind::List(item) => {
+            let Some(items) = value.as_array() else { return Err(format!("{}: expected an array", path)) };
+            for (i, entry) in items.iter().enumerate() {
+                check(item, entry, &format!("{}[{}]", path, i))?;
+            }
+            true
+        }
+        Kind::Object(fields) => {
+            let Some(object) = value.as_object() else { return Err(format!("{}: expected an object", path)) };
+            for field in fields.iter() {
+                let child = format!("{}{}{}", path, if path.is_empty() { "" } else { "." }, field.name);
+                match object.get(field.name) {
+                    Some(Value::Null) | None if field.required => return Err(format!("{}: missing", child)),
+                    Some(Value::Null) | None => {}
+                    Some(v) => check(&field.kind, v, &child)?,
+                }
+            }
+            true
+        }
+    };
+    if ok { Ok(()) } else { Err(format!("{}: wrong type", path)) }
+}
+
+/// Check a parsed response against the schema; unknown extra fields are allowed
+pub fn validate(value: &Value) -> Result<(), String> {
+    check(&Kind::Object(OUTPUT_FIELDS), value, "")
+}
+
+/// Same check for the analysis half alone, as returned by the analysis LLM
+pub fn validate_intelligence(value: &Value) -> Result<(), String> {
+    check(&Kind::Object(INTELLIGENCE_FIELDS), value, "intelligence")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing_engine::{keyword_intelligence, IntelligenceOutput};
+
+    #[test]
+    fn schema_covers_every_output_field() {
+        let mut output = keyword_intelligence("Speaker 1", "we must fix this asap");
+        output.intelligence.summary = Some("fix".into());
+        output.intelligence.entities = Some(vec![]);
+        output.intelligence.graph_updates = Some(vec![]);
+        let value = serde_json::to_value(&output).unwrap();
+        let schema = response_schema();
+
+        for key in value.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(key).is_some(), "schema missing {}", key);
+        }
+        for key in value["intelligence"].as_object().unwrap().keys() {
+            assert!(schema["properties"]["intelligence"]["properties"].get(key).is_some(), "schema missing {}", key);
+        }
+        assert_eq!(validate(&value), Ok(()));
+    }
+
+    #[test]
+    fn prompt_example_is_a_valid_output() {
+        let example: Value = serde_json::from_str(&example_object(OUTPUT_FIELDS)).unwrap();
+        assert_eq!(validate(&example), Ok(()));
+        assert!(serde_json::from_value::<IntelligenceOutput>(example).is_ok());
+    }
+
+    #[test]
+    fn prompt_lists_every_enum_value() {
+        let prompt = prompt_format(OUTPUT_FIELDS);
+        for choice in CATEGORIES.iter().chain(TONES) {
+            assert!(prompt.contains(&format!("{} = ", choice.value)), "prompt missing {}", choice.value);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_tone_and_missing_fields() {
+        let mut value = serde_json::to_value(keyword_intelligence("Speaker 1", "hello")).unwrap();
+        value["intelligence"]["tone"] = json!("CALM");
+        assert!(validate(&value).unwrap_err().starts_with("intelligence.tone"));
+
+        value["intelligence"]["tone"] = json!("NEUTRAL");
+        value.as_object_mut().unwrap().remove("speaker_id");
+        assert_eq!(validate(&value), Err("speaker_id: missing".to_string()));
+    }
+}
@@ -1,9 +1,26 @@
+mod audio_archive;
 mod audio_capture;
+mod conversation_context;
+mod diarization;
 mod gemini_client;
+mod gemini_live;
+mod intelligence_schema;
+mod llm_analysis;
 mod processing_engine;
+mod prompt_templates;
 mod session_manager;
-use audio_capture::AudioState;
-use gemini_client::GeminiState;
+mod transcription;
+mod vad;
+mod voiceprints;
+#[cfg(feature = "whisper")]
+mod whisper_provider;
+use audio_capture::{AudioChunk, AudioState};
+use diarization::{DiarizationConfig, DiarizationState};
+use gemini_client::{AudioBatchingConfig, GeminiState};
+use llm_analysis::{AnalysisConfig, AnalysisState};
+use processing_engine::{ProcessingEngineState, ProcessingSettings};
+use prompt_templates::{PromptState, PromptStore};
+use voiceprints::VoiceprintStore;
 use std::sync::Mutex;
 use crossbeam_channel::unbounded;
 use tauri::{
@@ -19,7 +36,7 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let (audio_tx, audio_rx) = unbounded::<Vec<f32>>();
+    let (audio_tx, audio_rx) = unbounded::<AudioChunk>();
 
     let audio_state = AudioState {
         audio_tx: Mutex::new(Some(audio_tx)),
@@ -28,6 +45,26 @@ pub fn run() {
 
     let gemini_state = GeminiState {
         audio_rx: Mutex::new(Some(audio_rx)),
+        batching: Mutex::new(AudioBatchingConfig::load()),
+        ..Default::default()
+    };
+
+    let analysis_state = AnalysisState {
+        config: Mutex::new(AnalysisConfig::load()),
+        ..Default::default()
+    };
+
+    let diarization_state = DiarizationState {
+        config: Mutex::new(DiarizationConfig::load()),
+        voiceprints: Mutex::new(VoiceprintStore::load()),
+        ..Default::default()
+    };
+
+    let prompt_state = PromptState {
+        store: Mutex::new(PromptStore::load()),
+    };
+    let processing_state = ProcessingEngineState {
+        settings: Mutex::new(ProcessingSettings::load()),
         ..Default::default()
     };
 
@@ -97,29 +134,65 @@ pub fn run() {
         })
         .manage(audio_state)
         .manage(gemini_state)
+        .manage(processing_state)
+        .manage(analysis_state)
+        .manage(prompt_state)
+        .manage(diarization_state)
         .invoke_handler(tauri::generate_handler![
             greet, 
             audio_capture::list_audio_devices,
+            audio_capture::select_audio_device,
             audio_capture::start_audio_capture,
             audio_capture::stop_audio_capture,
             audio_capture::set_capture_mode,
+            audio_capture::set_mixer_config,
             audio_capture::get_current_volume,
+            audio_capture::set_vad_config,
+            audio_capture::get_vad_state,
+            audio_archive::start_session_recording,
+            audio_archive::stop_session_recording,
+            audio_archive::list_session_recordings,
             gemini_client::test_gemini_connection,
             gemini_client::update_gemini_key,
             gemini_client::set_gemini_model,
             gemini_client::get_available_models,
+            gemini_client::transcribe_file,
+            gemini_client::get_batching_config,
+            gemini_client::update_batching_config,
+            gemini_client::get_pipeline_metrics,
+            gemini_client::reset_conversation_context,
+            diarization::get_diarization_config,
+            diarization::update_diarization_config,
+            diarization::get_speakers,
+            diarization::rename_speaker,
+            voiceprints::list_voice_profiles,
+            voiceprints::enroll_voice_file,
+            voiceprints::enroll_speaker_voice,
+            voiceprints::delete_voice_profile,
+            llm_analysis::get_analysis_config,
+            llm_analysis::update_analysis_config,
+            llm_analysis::test_analysis_endpoint,
             processing_engine::validate_json_schema,
+            processing_engine::get_processing_settings,
             processing_engine::update_processing_settings,
             processing_engine::get_recent_intelligence,
             processing_engine::clear_intelligence_cache,
             processing_engine::inject_manual_intelligence,
+            prompt_templates::get_prompt_templates,
+            prompt_templates::select_prompt_template,
+            prompt_templates::save_prompt_template,
+            prompt_templates::delete_prompt_template,
+            prompt_templates::update_prompt_variables,
+            prompt_templates::preview_prompt,
+            prompt_templates::snapshot_prompt,
             session_manager::save_session,
             session_manager::load_session,
             session_manager::list_sessions,
             session_manager::delete_session,
             session_manager::export_session,
             session_manager::generate_session_summary,
-            session_manager::get_session_summary
+            session_manager::get_session_summary,
+            session_manager::rename_session_speaker
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
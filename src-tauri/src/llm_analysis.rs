@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::intelligence_schema;
+use crate::processing_engine::{extract_json_objects, Intelligence};
+use crate::session_manager::{load_json, save_json_atomic};
+use crate::transcription::Transcription;
+
+// ============================================================================
+// LLM ANALYSIS - intelligence step against any OpenAI-compatible chat endpoint
+// ============================================================================
+
+const ANALYSIS_PROMPT_INTRO: &str = "You analyse one transcript chunk from a live meeting.";
+
+/// Asks for the `intelligence` object only; the format section comes from the schema
+fn analysis_prompt() -> String {
+    format!("{}\n\n{}", ANALYSIS_PROMPT_INTRO, intelligence_schema::prompt_format(intelligence_schema::INTELLIGENCE_FIELDS))
+}
+
+pub struct AnalysisState {
+    pub config: Mutex<AnalysisConfig>,
+    pub client: reqwest::Client, // Shared connection pool for the endpoint
+}
+
+impl Default for AnalysisState {
+    fn default() -> Self {
+        Self {
+            config: Mutex::new(AnalysisConfig::default()),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisConfig {
+    pub enabled: bool,           // Off = the transcription provider's own analysis is used
+    pub base_url: String,        // e.g. http://localhost:11434/v1 for Ollama
+    pub model: String,
+    pub api_key: Option<String>, // Local servers usually need none
+    pub temperature: f32,
+    pub timeout_secs: u64,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: "http://localhost:11434/v1".to_string(),
+            model: "llama3.1".to_string(),
+            api_key: None,
+            temperature: 0.1,
+            timeout_secs: 30,
+        }
+    }
+}
+
+impl AnalysisConfig {
+    const FILE: &'static str = "analysis_config.json";
+
+    pub fn load() -> Self {
+        load_json::<Self>(Self::FILE)
+            .filter(|c| c.validate().is_ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_json_atomic(Self::FILE, self)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let url = url::Url::parse(&self.base_url)
+            .map_err(|e| format!("Invalid base URL {}: {}", self.base_url, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Base URL must be http(s): {}", self.base_url));
+        }
+        if self.model.trim().is_empty() {
+            return Err("Model name is required".into());
+        }
+        if !(0.0..=2.0).contains(&self.temperature) {
+            return Err(format!("Temperature out of range (0-2): {}", self.temperature));
+        }
+        if !(1..=300).contains(&self.timeout_secs) {
+            return Err(format!("Timeout out of range (1-300s): {}", self.timeout_secs));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// CHAT COMPLETIONS
+// ============================================================================
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    temperature: f32,
+    response_format: ResponseFormat,
+    stream: bool,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> { role: &'a str, content: &'a str }
+
+#[derive(Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    format_type: &'static str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse { choices: Vec<ChatChoice> }
+
+#[derive(Deserialize)]
+struct ChatChoice { message: ChatReply }
+
+#[derive(Deserialize)]
+struct ChatReply { content: Option<String> }
+
+/// Run the analysis half on transcript text and return the validated `Intelligence`
+pub async fn analyze_transcript(
+    client: &reqwest::Client,
+    config: &AnalysisConfig,
+    speaker_id: &str,
+    text: &str,
+) -> Result<Intelligence, String> {
+    let user = format!("{}: {}", speaker_id, text);
+    let system = analysis_prompt();
+    let request = ChatRequest {
+        model: &config.model,
+        messages: vec![
+            ChatMessage { role: "system", content: &system },
+            ChatMessage { role: "user", content: &user },
+        ],
+        temperature: config.temperature,
+        response_format: ResponseFormat { format_type: "json_object" },
+        stream: false,
+    };
+
+    let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
+    let mut builder = client.post(&url)
+        .json(&request)
+        .timeout(Duration::from_secs(config.timeout_secs));
+    if let Some(key) = config.api_key.as_deref().filter(|k| !k.is_empty()) {
+        builder = builder.bearer_auth(key);
+    }
+
+    let response = builder.send().await.map_err(|e| format!("HTTP: {}", e))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| format!("Read: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("HTTP {}: {}", status, body.chars().take(200).collect::<String>()));
+    }
+
+    let content = serde_json::from_str::<ChatResponse>(&body)
+        .map_err(|e| format!("Unexpected response: {}", e))?
+        .choices.into_iter().next()
+        .and_then(|c| c.message.content)
+        .ok_or("Empty completion")?;
+
+    // Some local models still wrap JSON in a markdown fence despite JSON mode
+    let object = extract_json_objects(&content).0.into_iter().next()
+        .ok_or("Schema validation failed: no JSON object in completion")?;
+    intelligence_schema::validate_intelligence(&object)?;
+    serde_json::from_value(object).map_err(|e| format!("Schema validation failed: {}", e))
+}
+
+/// Replace a transcription's intelligence with the LLM's analysis. Silence
+/// and any endpoint failure leave it untouched.
+pub async fn reanalyze_response(state: &AnalysisState, mut transcription: Transcription) -> Transcription {
+    let config = state.config.lock().unwrap().clone();
+    if !config.enabled || transcription.is_silence() { return transcription; }
+
+    let speaker_id = transcription.speaker_id.as_deref().unwrap_or("Speaker 1");
+    match analyze_transcript(&state.client, &config, speaker_id, &transcription.text).await {
+        Ok(intelligence) => transcription.intelligence = Some(intelligence),
+        Err(e) => println!("[ANALYSIS] ✗ {} - keeping transcription provider's analysis", e),
+    }
+    transcription
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_analysis_config(state: tauri::State<'_, AnalysisState>) -> Result<AnalysisConfig, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(config.clone())
+}
+
+/// Validates, persists and applies from the next segment
+#[tauri::command]
+pub fn update_analysis_config(
+    state: tauri::State<'_, AnalysisState>,
+    config: AnalysisConfig,
+) -> Result<AnalysisConfig, String> {
+    config.validate()?;
+    let mut current = state.config.lock().map_err(|e| e.to_string())?;
+    config.save()?;
+    *current = config.clone();
+    println!("[ANALYSIS] {} via {} ({})",
+             if config.enabled { "Enabled" } else { "Disabled" }, config.base_url, config.model);
+    Ok(config)
+}
+
+/// Runs one sample sentence through `config` (not yet saved) so the endpoint can be checked
+#[tauri::command]
+pub async fn test_analysis_endpoint(
+    state: tauri::State<'_, AnalysisState>,
+    config: AnalysisConfig,
+    text: Option<String>,
+) -> Result<Intelligence, String> {
+    config.validate()?;
+    let text = text.unwrap_or_else(|| "Ali will send the budget report by Friday.".to_string());
+    analyze_transcript(&state.client, &config, "Speaker 1", &text).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// Answers one request with `status` and `body`; the handle yields the request received
+    async fn mock_endpoint(status: u16, body: String) -> (AnalysisConfig, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (mut tcp, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = tcp.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                let Some(head_end) = text.find("\r\n\r\n") else { continue };
+                let length = text[..head_end].lines()
+                    .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if n == 0 || request.len() >= head_end + 4 + length { break; }
+            }
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status, body.len(), body,
+            );
+            tcp.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let config = AnalysisConfig {
+            enabled: true,
+            base_url: format!("http://127.0.0.1:{}/v1", port),
+            api_key: Some("secret".into()),
+            timeout_secs: 5,
+            ..Default::default()
+        };
+        (config, server)
+    }
+
+    fn completion(content: &str) -> String {
+        serde_json::json!({"choices": [{"message": {"content": content}}]}).to_string()
+    }
+
+    #[tokio::test]
+    async fn reads_analysis_wrapped_in_a_markdown_fence() {
+        let content = "```json\n{\"category\":[\"TASK\"],\"tone\":\"NEUTRAL\",\"confidence\":0.8}\n```";
+        let (config, server) = mock_endpoint(200, completion(content)).await;
+
+        let client = reqwest::Client::new();
+        let intelligence = analyze_transcript(&client, &config, "Ali", "send the report").await.unwrap();
+        assert_eq!(intelligence.category, vec!["TASK"]);
+        assert_eq!(intelligence.confidence, 0.8);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions"));
+        assert!(request.to_lowercase().contains("authorization: bearer secret"));
+        assert!(request.contains("Ali: send the report"));
+    }
+
+    #[tokio::test]
+    async fn error_status_reports_a_body_excerpt() {
+        let body = format!("model llama3.1 not found{}", " ".repeat(500));
+        let (config, server) = mock_endpoint(404, body).await;
+
+        let client = reqwest::Client::new();
+        let error = analyze_transcript(&client, &config, "Ali", "hello").await.unwrap_err();
+        assert!(error.starts_with("HTTP 404"), "{}", error);
+        assert!(error.contains("model llama3.1 not found"));
+        assert!(error.len() < 260);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reanalysis_failure_keeps_the_original_transcription() {
+        let (config, server) = mock_endpoint(200, completion("not json at all")).await;
+        let state = AnalysisState { config: Mutex::new(config), ..Default::default() };
+        let original = Transcription {
+            text: "we decided to ship".into(),
+            speaker_id: Some("Sara".into()),
+            intelligence: None,
+        };
+
+        let result = reanalyze_response(&state, original).await;
+        assert_eq!(result.text, "we decided to ship");
+        assert_eq!(result.speaker_id.as_deref(), Some("Sara"));
+        assert!(result.intelligence.is_none());
+        server.await.unwrap();
+    }
+}
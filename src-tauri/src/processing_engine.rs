@@ -2,6 +2,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::intelligence_schema;
+use crate::session_manager::{load_json, save_json_atomic};
+use crate::transcription::Transcription;
 
 // ============================================================================
 // STATION 3: OMNIPOTENT PROCESSING ENGINE
@@ -15,6 +20,18 @@ pub struct IntelligenceOutput {
     pub transcript_chunk: String,
     pub is_final: bool,
     pub intelligence: Intelligence,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<SegmentTiming>, // Filled in by the client, never by the model
+}
+
+/// When the audio behind an output was spoken
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct SegmentTiming {
+    pub sequence: u32,
+    pub start_ms: u64,      // Relative to capture start
+    pub end_ms: u64,
+    pub wall_start_ms: u64, // Unix epoch
+    pub wall_end_ms: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -61,33 +78,317 @@ pub struct GraphUpdate {
 // VALIDATION
 // ============================================================================
 
-const VALID_CATEGORIES: &[&str] = &[
-    "TASK", "DECISION", "DEADLINE", "QUERY", "ACTION_ITEM", "RISK",
-    "SENTIMENT", "URGENCY", "INTERRUPTION", "AGREEMENT", "DISAGREEMENT",
-    "OFF_TOPIC", "EMOTION_SHIFT", "DOMINANCE_SHIFT", "EMPATHY_GAP", "TOPIC_DRIFT"
-];
-
-const VALID_TONES: &[&str] = &[
-    "URGENT", "FRUSTRATED", "EXCITED", "POSITIVE", "NEGATIVE",
-    "HESITANT", "DOMINANT", "EMPATHETIC", "NEUTRAL"
-];
-
-pub fn validate_intelligence_output(json: &str) -> Result<IntelligenceOutput, String> {
-    serde_json::from_str::<IntelligenceOutput>(json)
-        .map_err(|e| format!("Schema validation failed: {}", e))
-}
-
 pub fn validate_category(category: &[String]) -> bool {
-    category.iter().all(|c| VALID_CATEGORIES.contains(&c.as_str()))
+    category.iter().all(|c| intelligence_schema::is_category(c))
 }
 
 pub fn validate_tone(tone: &Option<String>) -> bool {
     match tone {
-        Some(t) => VALID_TONES.contains(&t.as_str()),
+        Some(t) => intelligence_schema::is_tone(t),
         None => true,
     }
 }
 
+// ============================================================================
+// RESPONSE REPAIR
+// ============================================================================
+
+/// What had to be fixed before a model response would parse
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct RepairReport {
+    pub stripped_fences: bool,
+    pub dropped_text: bool,          // Prose around the JSON was discarded
+    pub merged_objects: usize,       // Extra top-level objects folded into the first
+    pub closed_truncation: bool,     // Output was cut off and closed by hand
+    pub mapped_fields: Vec<String>,  // e.g. "transcript -> transcript_chunk"
+}
+
+impl RepairReport {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl std::fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if self.stripped_fences { parts.push("stripped fences".to_string()); }
+        if self.dropped_text { parts.push("dropped surrounding text".to_string()); }
+        if self.merged_objects > 0 { parts.push(format!("merged {} extra object(s)", self.merged_objects)); }
+        if self.closed_truncation { parts.push("closed truncated JSON".to_string()); }
+        if !self.mapped_fields.is_empty() { parts.push(format!("mapped {}", self.mapped_fields.join(", "))); }
+        write!(f, "{}", if parts.is_empty() { "clean".to_string() } else { parts.join("; ") })
+    }
+}
+
+/// Contents of the first ``` fence (language tag dropped), or `None` without one.
+/// An unterminated fence runs to the end, as happens when output is cut off.
+fn strip_fences(raw: &str) -> Option<(&str, bool)> {
+    let start = raw.find("```")?;
+    let body = &raw[start + 3..];
+    let body = body.trim_start_matches(|c: char| c.is_ascii_alphanumeric());
+    let (inner, rest) = match body.find("```") {
+        Some(end) => (&body[..end], &body[end + 3..]),
+        None => (body, ""),
+    };
+    let outside = !raw[..start].trim().is_empty() || !rest.trim().is_empty();
+    Some((inner, outside))
+}
+
+/// Append closers for a truncated object and parse it. Tries closing at the
+/// cut first, then falls back to the last complete member before it.
+fn close_truncated(
+    fragment: &str,
+    in_string: bool,
+    escaped: bool,
+    stack: &[char],
+    last_comma: Option<(usize, Vec<char>)>,
+) -> Option<serde_json::Value> {
+    let close = |mut text: String, open: &[char]| {
+        for closer in open.iter().rev() {
+            text.push(*closer);
+        }
+        serde_json::from_str::<serde_json::Value>(&text).ok()
+    };
+
+    let mut text = fragment.to_string();
+    if in_string {
+        if escaped { text.pop(); } // A dangling backslash would escape the closing quote
+        text.push('"');
+    }
+    let trimmed = text.trim_end();
+    let mut text = trimmed.strip_suffix(',').unwrap_or(trimmed).to_string();
+    if text.ends_with(':') {
+        text.push_str("null");
+    }
+    if let Some(value) = close(text, stack) {
+        return Some(value);
+    }
+
+    // Cut inside a number, literal or key: drop the incomplete member
+    let (comma, open) = last_comma?;
+    close(fragment[..comma].to_string(), &open)
+}
+
+/// Every top-level JSON object in `text`, in order. A final object that never
+/// closes is repaired if possible. Returns (objects, closed_truncation, other_text).
+fn scan_objects(text: &str) -> (Vec<serde_json::Value>, bool, bool) {
+    let mut objects = Vec::new();
+    let mut other_text = false;
+    let mut stack: Vec<char> = Vec::new();
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut last_comma = None; // (offset in object, open brackets) at the last structural comma
+
+    for (i, c) in text.char_indices() {
+        if stack.is_empty() {
+            match c {
+                '{' => {
+                    start = i;
+                    stack.push('}');
+                    last_comma = None;
+                }
+                c if c.is_whitespace() || c == ',' => {}
+                _ => other_text = true,
+            }
+            continue;
+        }
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => stack.push('}'),
+            '[' => stack.push(']'),
+            '}' | ']' => {
+                stack.pop();
+                if stack.is_empty() {
+                    match serde_json::from_str(&text[start..=i]) {
+                        Ok(value) => objects.push(value),
+                        Err(_) => other_text = true,
+                    }
+                }
+            }
+            ',' => last_comma = Some((i - start, stack.clone())),
+            _ => {}
+        }
+    }
+
+    let mut closed = false;
+    if !stack.is_empty() {
+        match close_truncated(&text[start..], in_string, escaped, &stack, last_comma) {
+            Some(value) => {
+                objects.push(value);
+                closed = true;
+            }
+            None => other_text = true,
+        }
+    }
+    (objects, closed, other_text)
+}
+
+/// Pull the JSON object(s) out of a model response without interpreting them
+pub fn extract_json_objects(raw: &str) -> (Vec<serde_json::Value>, RepairReport) {
+    let mut report = RepairReport::default();
+    let mut text = raw.trim();
+    if let Some((inner, outside)) = strip_fences(text) {
+        report.stripped_fences = true;
+        report.dropped_text = outside;
+        text = inner;
+    }
+
+    let (objects, closed, other_text) = scan_objects(text);
+    report.closed_truncation = closed;
+    report.dropped_text |= other_text;
+    (objects, report)
+}
+
+fn rename_field(object: &mut serde_json::Map<String, serde_json::Value>, from: &str, to: &str, report: &mut RepairReport) {
+    if object.contains_key(to) { return; }
+    if let Some(value) = object.remove(from) {
+        object.insert(to.to_string(), value);
+        report.mapped_fields.push(format!("{} -> {}", from, to));
+    }
+}
+
+fn default_field(object: &mut serde_json::Map<String, serde_json::Value>, key: &str, value: serde_json::Value, report: &mut RepairReport) {
+    if object.get(key).is_none_or(|v| v.is_null()) {
+        object.insert(key.to_string(), value);
+        report.mapped_fields.push(format!("default {}", key));
+    }
+}
+
+/// Reshape loose model output onto `IntelligenceOutput`: alternate field
+/// names, analysis fields at the top level, a bare category string and
+/// lowercase enums. Missing metadata gets the same defaults the client sets.
+fn normalize_output(value: serde_json::Value, report: &mut RepairReport) -> Option<serde_json::Value> {
+    let serde_json::Value::Object(mut object) = value else { return None };
+
+    rename_field(&mut object, "transcript", "transcript_chunk", report);
+    rename_field(&mut object, "text", "transcript_chunk", report);
+    rename_field(&mut object, "speaker", "speaker_id", report);
+    if !object.contains_key("transcript_chunk") {
+        return None;
+    }
+    default_field(&mut object, "speaker_id", serde_json::json!("Speaker 1"), report);
+    default_field(&mut object, "timestamp_ms", serde_json::json!(0), report);
+    default_field(&mut object, "is_final", serde_json::json!(true), report);
+
+    if !object.get("intelligence").is_some_and(|v| v.is_object()) {
+        let mut intelligence = serde_json::Map::new();
+        for key in ["category", "summary", "tone", "confidence", "entities", "graph_updates"] {
+            if let Some(value) = object.remove(key) {
+                intelligence.insert(key.to_string(), value);
+            }
+        }
+        if !intelligence.is_empty() {
+            report.mapped_fields.push("top-level analysis -> intelligence".to_string());
+        }
+        object.insert("intelligence".to_string(), serde_json::Value::Object(intelligence));
+    }
+
+    let mut intel_report = RepairReport::default();
+    let intelligence = object.get_mut("intelligence").and_then(|v| v.as_object_mut())?;
+    match intelligence.remove("category") {
+        Some(serde_json::Value::String(single)) => {
+            intelligence.insert("category".into(), serde_json::json!([single]));
+            intel_report.mapped_fields.push("category string -> array".to_string());
+        }
+        Some(value) if !value.is_null() => { intelligence.insert("category".into(), value); }
+        _ => {}
+    }
+    default_field(intelligence, "category", serde_json::json!([]), &mut intel_report);
+    default_field(intelligence, "confidence", serde_json::json!(0.5), &mut intel_report);
+    default_field(intelligence, "tone", serde_json::json!("NEUTRAL"), &mut intel_report);
+
+    let uppercase = |v: &mut serde_json::Value| {
+        if let Some(s) = v.as_str().filter(|s| s.chars().any(|c| c.is_lowercase())) {
+            *v = serde_json::json!(s.to_uppercase());
+            return true;
+        }
+        false
+    };
+    let mut recased = false;
+    if let Some(tone) = intelligence.get_mut("tone") {
+        recased |= uppercase(tone);
+    }
+    if let Some(categories) = intelligence.get_mut("category").and_then(|v| v.as_array_mut()) {
+        for category in categories {
+            recased |= uppercase(category);
+        }
+    }
+    if recased {
+        intel_report.mapped_fields.push("uppercased enums".to_string());
+    }
+
+    report.mapped_fields.extend(intel_report.mapped_fields.into_iter().map(|f| format!("intelligence.{}", f)));
+    Some(serde_json::Value::Object(object))
+}
+
+/// Fold a later object's transcript and analysis into the first one
+fn merge_outputs(base: &mut IntelligenceOutput, extra: IntelligenceOutput) {
+    let text = extra.transcript_chunk.trim();
+    if !text.is_empty() {
+        if !base.transcript_chunk.is_empty() { base.transcript_chunk.push(' '); }
+        base.transcript_chunk.push_str(text);
+    }
+    let (into, from) = (&mut base.intelligence, extra.intelligence);
+    for category in from.category {
+        if !into.category.contains(&category) { into.category.push(category); }
+    }
+    into.confidence = into.confidence.min(from.confidence);
+    into.summary = into.summary.take().or(from.summary);
+    into.tone = into.tone.take().or(from.tone);
+    if let Some(entities) = from.entities {
+        into.entities.get_or_insert_with(Vec::new).extend(entities);
+    }
+    if let Some(updates) = from.graph_updates {
+        into.graph_updates.get_or_insert_with(Vec::new).extend(updates);
+    }
+}
+
+/// Parse a model response, repairing fences, extra prose, several objects,
+/// truncation and near-miss field names. Fails only if nothing usable is left.
+pub fn repair_intelligence_output(raw: &str) -> Result<(IntelligenceOutput, RepairReport), String> {
+    let (objects, mut report) = extract_json_objects(raw);
+    if objects.is_empty() {
+        return Err(format!("Schema validation failed: no JSON object in response ({} chars)", raw.len()));
+    }
+
+    let mut outputs = Vec::new();
+    let mut last_error = None;
+    for value in objects {
+        let Some(value) = normalize_output(value, &mut report) else { continue };
+        if let Err(e) = intelligence_schema::validate(&value) {
+            last_error = Some(e);
+            continue;
+        }
+        match serde_json::from_value::<IntelligenceOutput>(value) {
+            Ok(output) => outputs.push(output),
+            Err(e) => last_error = Some(e.to_string()),
+        }
+    }
+
+    let mut outputs = outputs.into_iter();
+    let mut output = outputs.next().ok_or_else(|| {
+        format!("Schema validation failed: {}", last_error.unwrap_or_else(|| "no transcript field".to_string()))
+    })?;
+    for extra in outputs {
+        merge_outputs(&mut output, extra);
+        report.merged_objects += 1;
+    }
+    let mut seen = std::collections::HashSet::new();
+    report.mapped_fields.retain(|f| seen.insert(f.clone())); // Same fix on every object
+    Ok((output, report))
+}
+
 // ============================================================================
 // PROCESSING ENGINE STATE
 // ============================================================================
@@ -216,11 +517,23 @@ impl Default for ProcessingSettings {
             prediction_aggression: 0.5,
             max_error_streak: 5,
             enable_optimistic: true,
-            categories_filter: VALID_CATEGORIES.iter().map(|s| s.to_string()).collect(),
+            categories_filter: intelligence_schema::category_values().map(String::from).collect(),
         }
     }
 }
 
+impl ProcessingSettings {
+    const FILE: &'static str = "processing_settings.json";
+
+    pub fn load() -> Self {
+        load_json(Self::FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_json_atomic(Self::FILE, self)
+    }
+}
+
 // ============================================================================
 // KNOWLEDGE GRAPH
 // ============================================================================
@@ -334,66 +647,83 @@ impl KnowledgeGraph {
 // PROCESSING FUNCTIONS
 // ============================================================================
 
-/// Process raw text from Gemini, validate, cache, and filter
+/// Validate, cache and filter a provider's transcription. Text that arrives
+/// without analysis is tagged by keyword and always kept.
+/// `previous_transcript` is set when the audio overlapped the last emitted batch;
+/// words repeated from its tail are stripped before anything else runs.
 pub fn process_intelligence(
     state: &ProcessingEngineState,
-    raw_text: &str,
+    transcription: Transcription,
+    previous_transcript: Option<&str>,
 ) -> Result<Option<IntelligenceOutput>, ProcessingError> {
     let settings = state.settings.lock().unwrap().clone();
+    let Transcription { mut text, speaker_id, intelligence } = transcription;
     
-    // Try to parse as JSON
-    match validate_intelligence_output(raw_text) {
-        Ok(output) => {
-            // Reset error streak on success
-            *state.error_streak.lock().unwrap() = 0;
-            
-            // Validate categories and tones
-            if !validate_category(&output.intelligence.category) {
-                return Err(ProcessingError::InvalidCategory);
-            }
-            if !validate_tone(&output.intelligence.tone) {
-                return Err(ProcessingError::InvalidTone);
-            }
-            
-            // Filter by confidence threshold
-            if output.intelligence.confidence < settings.confidence_threshold {
-                return Ok(None); // Below threshold, skip
-            }
-            
-            // Filter by categories
-            let has_matching_category = output.intelligence.category.iter()
-                .any(|c| settings.categories_filter.contains(c));
-            if !has_matching_category && !settings.categories_filter.is_empty() {
-                return Ok(None); // No matching category, skip
-            }
-            
-            // Add to cache
-            state.cache.lock().unwrap().add(output.clone());
-            
-            // Update graph with confirmed data
-            if let Some(updates) = &output.intelligence.graph_updates {
-                for update in updates {
-                    state.graph.add_edge(update.clone(), false); // confirmed
-                }
-            }
-            
-            // Confirm any matching optimistic predictions
-            state.graph.confirm_all_optimistic();
-            
-            Ok(Some(output))
+    if let Some(previous) = previous_transcript {
+        text = strip_overlap(previous, &text);
+        if text.is_empty() {
+            return Ok(None); // Entirely a repeat of the overlap
         }
-        Err(e) => {
-            // Increment error streak
-            let mut streak = state.error_streak.lock().unwrap();
-            *streak += 1;
-            
-            if *streak >= settings.max_error_streak {
-                return Err(ProcessingError::ErrorStreakExceeded(*streak));
-            }
-            
-            Err(ProcessingError::ParseError(e))
+    }
+    
+    let mut output = keyword_intelligence(speaker_id.as_deref().unwrap_or("Speaker 1"), &text);
+    let analysed = intelligence.is_some();
+    if let Some(intelligence) = intelligence {
+        output.intelligence = intelligence;
+    }
+    
+    // Reset error streak on success
+    *state.error_streak.lock().unwrap() = 0;
+    
+    // Validate categories and tones
+    if !validate_category(&output.intelligence.category) {
+        return Err(ProcessingError::InvalidCategory);
+    }
+    if !validate_tone(&output.intelligence.tone) {
+        return Err(ProcessingError::InvalidTone);
+    }
+    
+    // Filter by confidence threshold; keyword tags carry no judgement to filter on
+    if analysed && output.intelligence.confidence < settings.confidence_threshold {
+        return Ok(None); // Below threshold, skip
+    }
+    
+    // Filter by categories (uncategorised speech is never filtered out)
+    let has_matching_category = output.intelligence.category.iter()
+        .any(|c| settings.categories_filter.contains(c));
+    if !has_matching_category
+        && !output.intelligence.category.is_empty()
+        && !settings.categories_filter.is_empty()
+    {
+        return Ok(None); // No matching category, skip
+    }
+    
+    // Add to cache
+    state.cache.lock().unwrap().add(output.clone());
+    
+    // Update graph with confirmed data
+    if let Some(updates) = &output.intelligence.graph_updates {
+        for update in updates {
+            state.graph.add_edge(update.clone(), false); // confirmed
         }
     }
+    
+    // Confirm any matching optimistic predictions
+    state.graph.confirm_all_optimistic();
+    
+    Ok(Some(output))
+}
+
+/// Count a response the provider couldn't read; escalates once the streak hits the limit
+pub fn record_invalid_response(state: &ProcessingEngineState, error: String) -> ProcessingError {
+    let max_error_streak = state.settings.lock().unwrap().max_error_streak;
+    let mut streak = state.error_streak.lock().unwrap();
+    *streak += 1;
+    
+    if *streak >= max_error_streak {
+        return ProcessingError::ErrorStreakExceeded(*streak);
+    }
+    ProcessingError::ParseError(error)
 }
 
 /// Generate optimistic prediction from partial text
@@ -431,6 +761,28 @@ pub fn generate_optimistic(
     Some(prediction)
 }
 
+/// Wrap plain transcript text in the GOD schema for providers without an LLM,
+/// using keyword detection in place of model classification
+pub fn keyword_intelligence(speaker_id: &str, text: &str) -> IntelligenceOutput {
+    let category = detect_category_keywords(text);
+    let tone = if category.as_deref() == Some("URGENCY") { "URGENT" } else { "NEUTRAL" };
+    IntelligenceOutput {
+        timestamp_ms: now_ms(),
+        speaker_id: speaker_id.to_string(),
+        transcript_chunk: text.trim().to_string(),
+        is_final: true,
+        intelligence: Intelligence {
+            category: category.into_iter().collect(),
+            summary: None,
+            tone: Some(tone.to_string()),
+            confidence: 0.6, // Keyword tags are a hint, not a judgement
+            entities: None,
+            graph_updates: None,
+        },
+        segment: None,
+    }
+}
+
 /// Simple keyword detection for instant category hints
 fn detect_category_keywords(text: &str) -> Option<String> {
     let text_lower = text.to_lowercase();
@@ -481,11 +833,52 @@ pub enum ProcessingError {
     ErrorStreakExceeded(u32),
 }
 
+impl std::fmt::Display for ProcessingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessingError::ParseError(e) => write!(f, "{}", e),
+            ProcessingError::InvalidCategory => write!(f, "Invalid category"),
+            ProcessingError::InvalidTone => write!(f, "Invalid tone"),
+            ProcessingError::ErrorStreakExceeded(n) => write!(f, "{} invalid responses in a row", n),
+        }
+    }
+}
+
 // ============================================================================
 // HELPERS
 // ============================================================================
 
-fn now_ms() -> u64 {
+/// Longest overlap (in words) looked for between consecutive batches
+const OVERLAP_MAX_WORDS: usize = 12;
+
+fn normalize_word(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Remove the leading words of `next` that repeat the end of `previous`, as
+/// happens when overlapping audio is transcribed twice. Matching ignores case
+/// and punctuation; a single-word match must be 4+ characters to count.
+pub fn strip_overlap(previous: &str, next: &str) -> String {
+    let prev: Vec<String> = previous.split_whitespace().map(normalize_word).filter(|w| !w.is_empty()).collect();
+    let next_words: Vec<&str> = next.split_whitespace().collect();
+    let next_norm: Vec<String> = next_words.iter().map(|w| normalize_word(w)).collect();
+
+    let max = OVERLAP_MAX_WORDS.min(prev.len()).min(next_words.len());
+    for k in (1..=max).rev() {
+        // Punctuation-only tokens at the start of `next` don't count as words
+        let head: Vec<&String> = next_norm[..k].iter().filter(|w| !w.is_empty()).collect();
+        if head.is_empty() || head.len() == 1 && head[0].chars().count() < 4 {
+            continue;
+        }
+        let tail = &prev[prev.len() - head.len()..];
+        if tail.iter().zip(&head).all(|(a, b)| a == *b) {
+            return next_words[k..].join(" ");
+        }
+    }
+    next.trim().to_string()
+}
+
+pub(crate) fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -496,65 +889,231 @@ fn now_ms() -> u64 {
 // TAURI COMMANDS
 // ============================================================================
 
+/// Checks a response as the engine would read it, listing any repairs needed
 #[tauri::command]
-pub fn validate_json_schema(json_str: String) -> Result<bool, String> {
-    match validate_intelligence_output(&json_str) {
-        Ok(output) => {
-            if !validate_category(&output.intelligence.category) {
-                return Err("Invalid category".to_string());
-            }
-            if !validate_tone(&output.intelligence.tone) {
-                return Err("Invalid tone".to_string());
-            }
-            Ok(true)
-        }
-        Err(e) => Err(e),
+pub fn validate_json_schema(json_str: String) -> Result<RepairReport, String> {
+    let (output, report) = repair_intelligence_output(&json_str)?;
+    if !validate_category(&output.intelligence.category) {
+        return Err("Invalid category".to_string());
+    }
+    if !validate_tone(&output.intelligence.tone) {
+        return Err("Invalid tone".to_string());
     }
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn get_processing_settings(
+    state: tauri::State<'_, ProcessingEngineState>,
+) -> Result<ProcessingSettings, String> {
+    Ok(state.settings.lock().map_err(|e| e.to_string())?.clone())
 }
 
 #[tauri::command]
 pub fn update_processing_settings(
+    state: tauri::State<'_, ProcessingEngineState>,
     confidence_threshold: Option<f32>,
     prediction_aggression: Option<f32>,
     enable_optimistic: Option<bool>,
     categories: Option<Vec<String>>,
 ) -> Result<String, String> {
-    // This would update the global state - simplified for now
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mut updated = settings.clone();
+
+    if let Some(threshold) = confidence_threshold {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(format!("Confidence threshold out of range: {}", threshold));
+        }
+        updated.confidence_threshold = threshold;
+    }
+    if let Some(aggression) = prediction_aggression {
+        if !(0.0..=1.0).contains(&aggression) {
+            return Err(format!("Prediction aggression out of range: {}", aggression));
+        }
+        updated.prediction_aggression = aggression;
+    }
+    if let Some(enabled) = enable_optimistic {
+        updated.enable_optimistic = enabled;
+    }
+    if let Some(categories) = categories {
+        if !validate_category(&categories) {
+            return Err("Invalid category".to_string());
+        }
+        updated.categories_filter = categories;
+    }
+
+    updated.save()?;
+    *settings = updated;
     Ok("Settings updated".to_string())
 }
 
 #[tauri::command]
-pub fn get_recent_intelligence(count: u32) -> Result<Vec<String>, String> {
-    // Would return from cache - simplified for now
-    Ok(vec![])
+pub fn get_recent_intelligence(
+    state: tauri::State<'_, ProcessingEngineState>,
+    count: u32,
+) -> Result<Vec<IntelligenceOutput>, String> {
+    let cache = state.cache.lock().map_err(|e| e.to_string())?;
+    Ok(cache.get_recent(count as usize))
 }
 
 #[tauri::command]
-pub fn clear_intelligence_cache() -> Result<String, String> {
+pub fn clear_intelligence_cache(state: tauri::State<'_, ProcessingEngineState>) -> Result<String, String> {
+    state.cache.lock().map_err(|e| e.to_string())?.clear();
+    state.optimistic_buffer.lock().map_err(|e| e.to_string())?.clear();
+    *state.error_streak.lock().map_err(|e| e.to_string())? = 0;
+    state.graph.clear();
     Ok("Cache cleared".to_string())
 }
 
 #[tauri::command]
 pub fn inject_manual_intelligence(
+    state: tauri::State<'_, ProcessingEngineState>,
+    app: AppHandle,
     text: String,
     category: String,
     confidence: f32,
 ) -> Result<String, String> {
-    // Manual intelligence injection
+    // Manual intelligence injection - linked to its category in the graph
     let output = IntelligenceOutput {
         timestamp_ms: now_ms(),
         speaker_id: "MANUAL".to_string(),
-        transcript_chunk: text,
+        transcript_chunk: text.clone(),
         is_final: true,
         intelligence: Intelligence {
-            category: vec![category],
+            category: vec![category.clone()],
             summary: Some("Manually injected".to_string()),
             tone: Some("NEUTRAL".to_string()),
             confidence,
             entities: None,
-            graph_updates: None,
+            graph_updates: Some(vec![GraphUpdate {
+                node_a: category,
+                relation: "mentions".to_string(),
+                node_b: text.chars().take(30).collect(),
+                weight: Some(confidence),
+                directional: Some(true),
+                tone_modifier: None,
+            }]),
         },
+        segment: None,
     };
-    
-    Ok(serde_json::to_string(&output).unwrap_or_default())
+
+    let json = serde_json::to_string(&output).map_err(|e| e.to_string())?;
+    match process_intelligence(&state, output.into(), None).map_err(|e| e.to_string())? {
+        Some(output) => {
+            let _ = app.emit("god:intelligence", &output);
+            Ok(json)
+        }
+        None => Err("Filtered out by current confidence/category settings".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_repeated_words_from_overlap() {
+        assert_eq!(
+            strip_overlap("we should ship the release on Friday", "on Friday, and then start planning"),
+            "and then start planning"
+        );
+    }
+
+    #[test]
+    fn overlap_match_ignores_case_and_punctuation() {
+        assert_eq!(strip_overlap("Let's review the budget.", "the Budget numbers look fine"), "numbers look fine");
+    }
+
+    #[test]
+    fn keeps_text_without_overlap() {
+        assert_eq!(strip_overlap("the meeting is over", "next item is hiring"), "next item is hiring");
+    }
+
+    #[test]
+    fn short_single_word_is_not_treated_as_overlap() {
+        assert_eq!(strip_overlap("send it to the", "the team by noon"), "the team by noon");
+        assert_eq!(strip_overlap("talk to marketing", "marketing agreed"), "agreed");
+    }
+
+    #[test]
+    fn fully_repeated_chunk_becomes_empty() {
+        assert_eq!(strip_overlap("we agreed on the deadline", "on the deadline"), "");
+    }
+
+    #[test]
+    fn repairs_fenced_response_with_prose() {
+        let raw = concat!(
+            "Here you go:\n```json\n",
+            r#"{"timestamp_ms":0,"speaker_id":"Speaker 1","transcript_chunk":"ship it","is_final":true,"#,
+            r#""intelligence":{"category":["DECISION"],"tone":"NEUTRAL","confidence":0.9}}"#,
+            "\n```",
+        );
+        let (output, report) = repair_intelligence_output(raw).unwrap();
+        assert_eq!(output.transcript_chunk, "ship it");
+        assert!(report.stripped_fences && report.dropped_text);
+        assert!(!report.closed_truncation && report.mapped_fields.is_empty());
+    }
+
+    #[test]
+    fn closes_response_truncated_mid_string() {
+        let raw = r#"{"timestamp_ms":0,"speaker_id":"Speaker 2","transcript_chunk":"we need the report by Fri"#;
+        let (output, report) = repair_intelligence_output(raw).unwrap();
+        assert_eq!(output.transcript_chunk, "we need the report by Fri");
+        assert!(report.closed_truncation);
+        assert!(output.intelligence.category.is_empty());
+    }
+
+    #[test]
+    fn drops_member_cut_inside_a_number() {
+        let raw = r#"{"speaker_id":"Speaker 1","transcript_chunk":"ok","intelligence":{"category":["TASK"],"tone":"NEUTRAL","confidence":0."#;
+        let (output, report) = repair_intelligence_output(raw).unwrap();
+        assert_eq!(output.intelligence.category, vec!["TASK"]);
+        assert_eq!(output.intelligence.confidence, 0.5);
+        assert!(report.closed_truncation);
+    }
+
+    #[test]
+    fn maps_flat_prompt_shape() {
+        let raw = r#"{"speaker":"Speaker 3","transcript":"this is urgent","category":"urgency","tone":"urgent","confidence":0.8}"#;
+        let (output, report) = repair_intelligence_output(raw).unwrap();
+        assert_eq!(output.speaker_id, "Speaker 3");
+        assert_eq!(output.transcript_chunk, "this is urgent");
+        assert_eq!(output.intelligence.category, vec!["URGENCY"]);
+        assert_eq!(output.intelligence.tone.as_deref(), Some("URGENT"));
+        assert!(report.mapped_fields.contains(&"transcript -> transcript_chunk".to_string()));
+    }
+
+    #[test]
+    fn merges_several_objects() {
+        let raw = r#"{"transcript":"first part","category":["TASK"],"confidence":0.9}
+{"transcript":"second part","category":["DEADLINE"],"confidence":0.7}"#;
+        let (output, report) = repair_intelligence_output(raw).unwrap();
+        assert_eq!(output.transcript_chunk, "first part second part");
+        assert_eq!(output.intelligence.category, vec!["TASK", "DEADLINE"]);
+        assert_eq!(output.intelligence.confidence, 0.7);
+        assert_eq!(report.merged_objects, 1);
+    }
+
+    #[test]
+    fn clean_response_reports_nothing() {
+        let json = serde_json::to_string(&keyword_intelligence("Speaker 1", "the deadline is friday")).unwrap();
+        let (_, report) = repair_intelligence_output(&json).unwrap();
+        assert!(report.is_clean());
+        assert!(repair_intelligence_output("no json here").is_err());
+    }
+
+    #[test]
+    fn confidence_filter_never_drops_keyword_tagged_text() {
+        let state = ProcessingEngineState::default();
+        state.settings.lock().unwrap().confidence_threshold = 0.9;
+
+        let plain = Transcription { text: "we need to call the vendor".into(), ..Default::default() };
+        let output = process_intelligence(&state, plain, None).unwrap().unwrap();
+        assert_eq!(output.transcript_chunk, "we need to call the vendor");
+        assert_eq!(output.intelligence.category, vec!["TASK"]);
+
+        let mut analysed = keyword_intelligence("Speaker 2", "maybe next week");
+        analysed.intelligence.confidence = 0.4;
+        assert!(process_intelligence(&state, analysed.into(), None).unwrap().is_none());
+    }
 }
@@ -0,0 +1,397 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::intelligence_schema::{self, SCHEMA_VERSION};
+use crate::session_manager::{load_json, save_json_atomic, PromptTemplateRef};
+
+// ============================================================================
+// PROMPT TEMPLATES - user-editable system prompts per meeting type
+// ============================================================================
+
+/// Placeholders a template body may use
+const VARIABLES: &[&str] = &["participants", "glossary", "language_hints"];
+
+const MAX_BODY_CHARS: usize = 8000;
+
+/// Appended to every template after the generated format section, so edits
+/// can change the instructions but never the output contract
+const FIXED_RULES: &str = r#"- If silence/unclear: {"status":"silence"}, or an empty transcript_chunk when the output schema is enforced"#;
+
+pub const DEFAULT_TEMPLATE_ID: &str = "general";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub id: String,       // Slug, e.g. "standup"
+    pub name: String,
+    pub version: u32,     // Bumped on every edit
+    pub body: String,     // {{participants}}, {{glossary}} and {{language_hints}} are filled in
+    #[serde(default)]
+    pub builtin: bool,    // Built-ins can be edited but not deleted
+    pub updated_at: String,
+}
+
+/// Per-meeting values substituted into the selected template
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptVariables {
+    pub participants: Vec<String>,
+    pub glossary: Vec<String>,      // Names, products and jargon to spell exactly
+    pub language_hints: String,     // e.g. "English with Urdu code-switching"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptStore {
+    pub selected: String,
+    pub variables: PromptVariables,
+    pub templates: Vec<PromptTemplate>,
+}
+
+impl Default for PromptStore {
+    fn default() -> Self {
+        Self {
+            selected: DEFAULT_TEMPLATE_ID.to_string(),
+            variables: PromptVariables::default(),
+            templates: builtin_templates(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PromptState {
+    pub store: Mutex<PromptStore>,
+}
+
+fn builtin(id: &str, name: &str, body: &str) -> PromptTemplate {
+    PromptTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        version: 1,
+        body: body.to_string(),
+        builtin: true,
+        updated_at: String::new(),
+    }
+}
+
+fn builtin_templates() -> Vec<PromptTemplate> {
+    vec![
+        builtin(DEFAULT_TEMPLATE_ID, "General meeting", r#"You are a PASSIVE MEETING INTELLIGENCE ENGINE.
+
+RULES:
+- Transcribe accurately ({{language_hints}})
+- Participants: {{participants}}
+- Spell these exactly: {{glossary}}"#),
+        builtin("standup", "Daily standup", r#"You are a PASSIVE MEETING INTELLIGENCE ENGINE listening to a daily standup.
+
+RULES:
+- Transcribe accurately ({{language_hints}})
+- Participants: {{participants}}
+- Spell these exactly: {{glossary}}
+- Each update covers yesterday, today and blockers: tag blockers RISK, commitments ACTION_ITEM
+- Put the person giving the update in graph_updates as owner of what they commit to"#),
+        builtin("sales_call", "Sales call", r#"You are a PASSIVE MEETING INTELLIGENCE ENGINE listening to a sales call.
+
+RULES:
+- Transcribe accurately ({{language_hints}})
+- Participants: {{participants}}
+- Spell these exactly: {{glossary}}
+- Objections, budget limits and competitor mentions are RISK; buying signals are AGREEMENT
+- Follow-ups promised by either side are ACTION_ITEM with a DEADLINE when a date is given"#),
+        builtin("interview", "Interview", r#"You are a PASSIVE MEETING INTELLIGENCE ENGINE listening to a job interview.
+
+RULES:
+- Transcribe accurately ({{language_hints}})
+- Participants: {{participants}}
+- Spell these exactly: {{glossary}}
+- Interviewer questions are QUERY; keep answers uncategorised unless they contain a clear claim or concern
+- Summaries stay factual: what was said, not an assessment of the candidate"#),
+    ]
+}
+
+impl PromptTemplate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() || !self.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-') {
+            return Err(format!("Template id must be a lowercase slug (a-z, 0-9, _ or -): {:?}", self.id));
+        }
+        if self.name.trim().is_empty() {
+            return Err("Template name is required".into());
+        }
+        if self.body.trim().is_empty() {
+            return Err("Template body is empty".into());
+        }
+        if self.body.chars().count() > MAX_BODY_CHARS {
+            return Err(format!("Template body too long (max {} characters)", MAX_BODY_CHARS));
+        }
+        for placeholder in placeholders(&self.body) {
+            if !VARIABLES.contains(&placeholder) {
+                return Err(format!("Unknown variable {{{{{}}}}} (available: {})", placeholder, VARIABLES.join(", ")));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Names inside `{{...}}` in `body`
+fn placeholders(body: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else { break };
+        names.push(rest[start + 2..start + end].trim());
+        rest = &rest[start + end + 2..];
+    }
+    names
+}
+
+fn list_or(items: &[String], fallback: &str) -> String {
+    let items: Vec<&str> = items.iter().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+    if items.is_empty() { fallback.to_string() } else { items.join(", ") }
+}
+
+impl PromptStore {
+    const FILE: &'static str = "prompt_templates.json";
+
+    /// Load the persisted store, adding any built-in template it lacks
+    pub fn load() -> Self {
+        let mut store = load_json::<Self>(Self::FILE).unwrap_or_default();
+
+        store.templates.retain(|t| t.validate().is_ok());
+        for template in builtin_templates() {
+            if !store.templates.iter().any(|t| t.id == template.id) {
+                store.templates.push(template);
+            }
+        }
+        if store.get(&store.selected).is_none() {
+            store.selected = DEFAULT_TEMPLATE_ID.to_string();
+        }
+        store
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_json_atomic(Self::FILE, self)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PromptTemplate> {
+        self.templates.iter().find(|t| t.id == id)
+    }
+
+    fn selected_template(&self) -> &PromptTemplate {
+        self.get(&self.selected)
+            .or_else(|| self.templates.first())
+            .expect("built-in templates are always present")
+    }
+
+    /// Snapshot of the prompt in effect, for recording in a session
+    pub fn selected_ref(&self) -> PromptTemplateRef {
+        let template = self.selected_template();
+        PromptTemplateRef {
+            id: template.id.clone(),
+            version: template.version,
+            schema_version: SCHEMA_VERSION,
+            variables: self.variables.clone(),
+            rendered: self.render_template(template),
+            captured_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Full system prompt: template with variables filled in, then the
+    /// schema's format section and the fixed rules
+    pub fn render_template(&self, template: &PromptTemplate) -> String {
+        let vars = &self.variables;
+        let language = if vars.language_hints.trim().is_empty() { "English/Urdu/Hindi" } else { vars.language_hints.trim() };
+        let body = template.body
+            .replace("{{participants}}", &list_or(&vars.participants, "unknown, label them Speaker 1, Speaker 2, ..."))
+            .replace("{{glossary}}", &list_or(&vars.glossary, "no special terms"))
+            .replace("{{language_hints}}", language);
+
+        format!(
+            "{}\n\n{}\n\nALWAYS:\n{}",
+            body.trim(),
+            intelligence_schema::prompt_format(intelligence_schema::OUTPUT_FIELDS),
+            FIXED_RULES
+        )
+    }
+
+    pub fn render(&self) -> String {
+        self.render_template(self.selected_template())
+    }
+
+    /// Insert a new template or update an existing one, bumping its version
+    /// when the name or body changed
+    pub fn upsert(&mut self, id: String, name: String, body: String) -> Result<PromptTemplate, String> {
+        let now = Utc::now().to_rfc3339();
+        let template = match self.templates.iter().position(|t| t.id == id) {
+            Some(index) => {
+                let current = &self.templates[index];
+                let changed = current.name != name || current.body != body;
+                PromptTemplate {
+                    version: current.version + changed as u32,
+                    updated_at: if changed { now } else { current.updated_at.clone() },
+                    name,
+                    body,
+                    ..current.clone()
+                }
+            }
+            None => PromptTemplate { id, name, version: 1, body, builtin: false, updated_at: now },
+        };
+        template.validate()?;
+
+        match self.templates.iter_mut().find(|t| t.id == template.id) {
+            Some(existing) => *existing = template.clone(),
+            None => self.templates.push(template.clone()),
+        }
+        Ok(template)
+    }
+}
+
+impl PromptState {
+    pub fn render(&self) -> String {
+        self.store.lock().unwrap().render()
+    }
+
+    pub fn selected_ref(&self) -> PromptTemplateRef {
+        self.store.lock().unwrap().selected_ref()
+    }
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_prompt_templates(state: tauri::State<'_, PromptState>) -> Result<PromptStore, String> {
+    Ok(state.store.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Applies from the next request
+#[tauri::command]
+pub fn select_prompt_template(state: tauri::State<'_, PromptState>, id: String) -> Result<PromptTemplateRef, String> {
+    let mut store = state.store.lock().map_err(|e| e.to_string())?;
+    if store.get(&id).is_none() {
+        return Err(format!("Unknown prompt template: {}", id));
+    }
+    let previous = std::mem::replace(&mut store.selected, id);
+    if let Err(e) = store.save() {
+        store.selected = previous;
+        return Err(e);
+    }
+    let selected = store.selected_ref();
+    println!("[PROMPT] Selected {} v{}", selected.id, selected.version);
+    Ok(selected)
+}
+
+/// Creates the template if `id` is new, otherwise edits it
+#[tauri::command]
+pub fn save_prompt_template(
+    state: tauri::State<'_, PromptState>,
+    id: String,
+    name: String,
+    body: String,
+) -> Result<PromptTemplate, String> {
+    let mut store = state.store.lock().map_err(|e| e.to_string())?;
+    let mut updated = store.clone();
+    let template = updated.upsert(id, name, body)?;
+    updated.save()?;
+    *store = updated;
+    println!("[PROMPT] Saved {} v{}", template.id, template.version);
+    Ok(template)
+}
+
+#[tauri::command]
+pub fn delete_prompt_template(state: tauri::State<'_, PromptState>, id: String) -> Result<(), String> {
+    let mut store = state.store.lock().map_err(|e| e.to_string())?;
+    match store.get(&id) {
+        None => return Err(format!("Unknown prompt template: {}", id)),
+        Some(t) if t.builtin => return Err(format!("Built-in template {} can't be deleted", id)),
+        Some(_) => {}
+    }
+    let mut updated = store.clone();
+    updated.templates.retain(|t| t.id != id);
+    if updated.selected == id {
+        updated.selected = DEFAULT_TEMPLATE_ID.to_string();
+    }
+    updated.save()?;
+    *store = updated;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_prompt_variables(
+    state: tauri::State<'_, PromptState>,
+    variables: PromptVariables,
+) -> Result<PromptVariables, String> {
+    let mut store = state.store.lock().map_err(|e| e.to_string())?;
+    let mut updated = store.clone();
+    updated.variables = variables.clone();
+    updated.save()?;
+    *store = updated;
+    Ok(variables)
+}
+
+/// Called when recording starts; the client stores the result in its session
+#[tauri::command]
+pub fn snapshot_prompt(state: tauri::State<'_, PromptState>) -> Result<PromptTemplateRef, String> {
+    Ok(state.store.lock().map_err(|e| e.to_string())?.selected_ref())
+}
+
+/// The exact system prompt `id` (default: the selected template) would send
+#[tauri::command]
+pub fn preview_prompt(state: tauri::State<'_, PromptState>, id: Option<String>) -> Result<String, String> {
+    let store = state.store.lock().map_err(|e| e.to_string())?;
+    let template = match id {
+        Some(id) => store.get(&id).ok_or_else(|| format!("Unknown prompt template: {}", id))?,
+        None => store.selected_template(),
+    };
+    Ok(store.render_template(template))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_templates_render_every_variable() {
+        let mut store = PromptStore::default();
+        store.variables.participants = vec!["Ali".into(), "Sara".into()];
+        for template in &store.templates {
+            assert_eq!(template.validate(), Ok(()));
+            let prompt = store.render_template(template);
+            assert!(!prompt.contains("{{"), "{} left a placeholder", template.id);
+            assert!(prompt.contains("Participants: Ali, Sara"));
+            assert!(prompt.contains(&format!("schema v{}", SCHEMA_VERSION)));
+        }
+    }
+
+    #[test]
+    fn editing_bumps_version_only_on_change() {
+        let mut store = PromptStore::default();
+        let body = store.get("standup").unwrap().body.clone();
+        assert_eq!(store.upsert("standup".into(), "Daily standup".into(), body.clone()).unwrap().version, 1);
+
+        let edited = store.upsert("standup".into(), "Daily standup".into(), format!("{}\n- Be brief", body)).unwrap();
+        assert_eq!((edited.version, edited.builtin), (2, true));
+
+        let created = store.upsert("retro".into(), "Retro".into(), "Listen to {{participants}}".into()).unwrap();
+        assert_eq!((created.version, created.builtin), (1, false));
+        assert!(store.upsert("retro".into(), "Retro".into(), "Hi {{team}}".into()).is_err());
+        assert_eq!(store.get("retro").unwrap().version, 1);
+    }
+
+    #[test]
+    fn snapshot_reproduces_prompt_after_edits() {
+        let mut store = PromptStore::default();
+        store.variables.glossary = vec!["Kubernetes".into()];
+        let snapshot = store.selected_ref();
+        assert_eq!(snapshot.rendered, store.render());
+
+        let body = store.get(DEFAULT_TEMPLATE_ID).unwrap().body.clone();
+        store.upsert(DEFAULT_TEMPLATE_ID.into(), "General meeting".into(), format!("{}\n- Be brief", body)).unwrap();
+        store.variables.glossary.clear();
+
+        assert_ne!(snapshot.rendered, store.render());
+        assert_eq!(snapshot.version, 1);
+        assert_eq!(snapshot.variables.glossary, vec!["Kubernetes"]);
+        assert!(snapshot.rendered.contains("Spell these exactly: Kubernetes"));
+    }
+}
@@ -1,8 +1,11 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use crate::audio_archive::validate_session_id;
+use crate::prompt_templates::{PromptState, PromptVariables};
 
 // ============================================================================
 // STATION 5: COSMIC POST-PROCESSING & EMPIRE
@@ -23,6 +26,23 @@ pub struct SessionData {
     pub psychosomatic: Option<PsychosomaticState>,
     #[serde(default)]
     pub insights: Option<ExtractedInsights>,
+    #[serde(default)]
+    pub prompt_template: Option<PromptTemplateRef>, // Prompt in effect when recording started
+}
+
+/// Which prompt produced a session's intelligence, with everything needed to
+/// reproduce it after the template has been edited
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PromptTemplateRef {
+    pub id: String,
+    pub version: u32,
+    pub schema_version: u32,
+    #[serde(default)]
+    pub variables: PromptVariables,
+    #[serde(default)]
+    pub rendered: String,    // Exact system prompt sent; empty in older sessions
+    #[serde(default)]
+    pub captured_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -49,6 +69,10 @@ pub struct TranscriptEntry {
     pub tone: Option<String>,
     pub category: Option<Vec<String>>,
     pub confidence: f32,
+    #[serde(default)]
+    pub start_ms: Option<u64>, // Speech time relative to session start
+    #[serde(default)]
+    pub end_ms: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -114,12 +138,34 @@ impl SessionData {
             summary: None,
             psychosomatic: None,
             insights: None,
+            prompt_template: None,
         }
     }
 
     pub fn add_transcript(&mut self, entry: TranscriptEntry) {
         self.transcripts.push(entry);
         self.metadata.total_transcripts = self.transcripts.len();
+        self.count_speakers();
+        self.updated_at = Utc::now().to_rfc3339();
+    }
+
+    /// Distinct speaker labels across the transcript
+    pub fn count_speakers(&mut self) {
+        let speakers: HashSet<&str> = self.transcripts.iter().map(|t| t.speaker_id.as_str()).collect();
+        self.metadata.total_speakers = speakers.len();
+    }
+
+    /// Relabel a speaker in the transcript and any summary assignments
+    pub fn rename_speaker(&mut self, from: &str, to: &str) {
+        for t in self.transcripts.iter_mut().filter(|t| t.speaker_id == from) {
+            t.speaker_id = to.to_string();
+        }
+        if let Some(summary) = &mut self.summary {
+            for item in summary.action_items.iter_mut().filter(|i| i.assignee.as_deref() == Some(from)) {
+                item.assignee = Some(to.to_string());
+            }
+        }
+        self.count_speakers();
         self.updated_at = Utc::now().to_rfc3339();
     }
 
@@ -172,6 +218,42 @@ impl SessionData {
     }
 }
 
+// App data files
+
+/// Everything the app persists lives under this directory
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("GOD-V8"))
+}
+
+/// Read a settings file from the data directory; `None` if missing or unparseable
+pub fn load_json<T: DeserializeOwned>(file: &str) -> Option<T> {
+    let json = fs::read_to_string(data_dir()?.join(file)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Write a settings file into the data directory without leaving it half-written
+pub fn save_json_atomic<T: Serialize>(file: &str, value: &T) -> Result<(), String> {
+    let dir = data_dir().ok_or("Could not find local data directory")?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    write_json_atomic(&dir.join(file), value)
+}
+
+/// Serialize next to `path`, then rename over it so readers never see a partial file
+fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+
+    let tmp_path = path.with_extension("tmp");
+
+    fs::write(&tmp_path, json)
+        .map_err(|e| format!("Failed to write temp file for {}: {}", name, e))?;
+
+    fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to commit {} (atomic rename): {}", name, e))
+}
+
 // Session Manager
 pub struct SessionManager {
     sessions_dir: PathBuf,
@@ -179,9 +261,8 @@ pub struct SessionManager {
 
 impl SessionManager {
     pub fn new() -> Result<Self, String> {
-        let sessions_dir = dirs::data_local_dir()
+        let sessions_dir = data_dir()
             .ok_or("Could not find local data directory")?
-            .join("GOD-V8")
             .join("sessions");
 
         fs::create_dir_all(&sessions_dir)
@@ -190,20 +271,20 @@ impl SessionManager {
         Ok(Self { sessions_dir })
     }
 
+    pub fn sessions_dir(&self) -> &Path {
+        &self.sessions_dir
+    }
+
+    /// Directory holding the archived audio segments of one session
+    pub fn audio_dir(&self, session_id: &str) -> PathBuf {
+        self.sessions_dir.join(session_id).join("audio")
+    }
+
     pub fn save_session(&self, session: &SessionData) -> Result<String, String> {
         let filename = format!("{}.json", session.id);
         let filepath = self.sessions_dir.join(&filename);
 
-        let json = serde_json::to_string_pretty(session)
-            .map_err(|e| format!("Failed to serialize session: {}", e))?;
-
-        let tmp_filepath = filepath.with_extension("tmp");
-
-        fs::write(&tmp_filepath, json)
-            .map_err(|e| format!("Failed to write temp session file: {}", e))?;
-
-        fs::rename(&tmp_filepath, &filepath)
-            .map_err(|e| format!("Failed to commit session file (atomic rename): {}", e))?;
+        write_json_atomic(&filepath, session)?;
 
         Ok(filepath.to_string_lossy().to_string())
     }
@@ -243,11 +324,19 @@ impl SessionManager {
     }
 
     pub fn delete_session(&self, session_id: &str) -> Result<(), String> {
+        validate_session_id(session_id)?;
         let filename = format!("{}.json", session_id);
         let filepath = self.sessions_dir.join(&filename);
 
         fs::remove_file(&filepath)
-            .map_err(|e| format!("Failed to delete session: {}", e))
+            .map_err(|e| format!("Failed to delete session: {}", e))?;
+
+        let session_dir = self.sessions_dir.join(session_id);
+        if session_dir.is_dir() {
+            fs::remove_dir_all(&session_dir)
+                .map_err(|e| format!("Failed to delete session audio: {}", e))?;
+        }
+        Ok(())
     }
 }
 
@@ -448,15 +537,38 @@ impl WebhookManager {
 // TAURI COMMANDS
 // ============================================================================
 
+/// The client stamps `prompt_template` when recording starts; sessions saved
+/// without one keep the one already on disk, or get the prompt in effect now
 #[tauri::command]
-pub fn save_session(session_json: String) -> Result<String, String> {
-    let session: SessionData = serde_json::from_str(&session_json)
+pub fn save_session(prompts: tauri::State<'_, PromptState>, session_json: String) -> Result<String, String> {
+    let mut session: SessionData = serde_json::from_str(&session_json)
         .map_err(|e| format!("Invalid session data: {}", e))?;
     
     let manager = SessionManager::new()?;
+    if session.prompt_template.is_none() {
+        session.prompt_template = manager.load_session(&session.id).ok()
+            .and_then(|saved| saved.prompt_template)
+            .or_else(|| Some(prompts.selected_ref()));
+    }
+    session.count_speakers();
     manager.save_session(&session)
 }
 
+/// Rename a speaker in a saved session, e.g. after naming them in review
+#[tauri::command]
+pub fn rename_session_speaker(session_id: String, from: String, to: String) -> Result<String, String> {
+    let to = to.trim();
+    if to.is_empty() {
+        return Err("Speaker name cannot be empty".into());
+    }
+    let manager = SessionManager::new()?;
+    let mut session = manager.load_session(&session_id)?;
+    session.rename_speaker(&from, to);
+    manager.save_session(&session)?;
+    serde_json::to_string(&session)
+        .map_err(|e| format!("Failed to serialize session: {}", e))
+}
+
 #[tauri::command]
 pub fn load_session(session_id: String) -> Result<String, String> {
     let manager = SessionManager::new()?;
@@ -0,0 +1,135 @@
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::processing_engine::{Intelligence, IntelligenceOutput};
+
+// ============================================================================
+// TRANSCRIPTION PROVIDERS - pluggable speech-to-intelligence backends
+// ============================================================================
+
+/// What a backend can do, so the audio loop and UI can adapt without knowing which one it is
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ProviderCapabilities {
+    pub structured_output: bool, // Analyses the speech as well as transcribing it
+    pub requires_api_key: bool,
+    pub offline: bool,
+    pub max_segment_secs: f32,   // Longest audio accepted in one request; caps batching
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderModel {
+    pub id: String,
+    pub name: String,
+}
+
+/// One finished speech segment, 16 kHz mono
+pub struct TranscriptionRequest<'a> {
+    pub api_key: &'a str,
+    pub model: &'a str,
+    pub audio: &'a [f32],
+    pub source_note: &'a str, // Hint about which capture channel dominated
+    pub system_prompt: &'a str, // Rendered prompt template; plain-text providers ignore it
+    pub context: &'a str,       // Recent transcript and speaker roster, may be empty
+}
+
+/// What a provider heard in one segment
+#[derive(Debug, Clone, Default)]
+pub struct Transcription {
+    pub text: String,                       // Empty when only silence was heard
+    pub speaker_id: Option<String>,         // The model's guess, if it makes one
+    pub intelligence: Option<Intelligence>, // None from plain speech-to-text providers
+}
+
+impl Transcription {
+    pub fn is_silence(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+}
+
+impl From<IntelligenceOutput> for Transcription {
+    fn from(output: IntelligenceOutput) -> Self {
+        Self {
+            text: output.transcript_chunk,
+            speaker_id: Some(output.speaker_id),
+            intelligence: Some(output.intelligence),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ProviderError {
+    RateLimited(String),
+    Unauthorized(String),
+    InvalidResponse(String), // Model output that doesn't fit the schema, even after repair
+    Failed(String),
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProviderError::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
+            ProviderError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            ProviderError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
+            ProviderError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// A backend turning a speech segment into a `Transcription` for
+/// `process_intelligence`. Pacing and retries stay with the caller.
+pub trait TranscriptionProvider: Send + Sync {
+    /// Stable identifier, e.g. "gemini"
+    fn id(&self) -> &'static str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// Models this provider serves; model ids must be unique across providers
+    fn models(&self) -> Vec<ProviderModel>;
+
+    fn transcribe<'a>(&'a self, request: TranscriptionRequest<'a>) -> BoxFuture<'a, Result<Transcription, ProviderError>>;
+
+    /// Cheap round trip used by the connection test
+    fn check<'a>(&'a self, api_key: &'a str, model: &'a str) -> BoxFuture<'a, Result<(), ProviderError>>;
+}
+
+// ============================================================================
+// REGISTRY
+// ============================================================================
+
+/// Every available provider; the selected model decides which one handles a segment
+#[derive(Clone, Default)]
+pub struct ProviderRegistry {
+    providers: Vec<Arc<dyn TranscriptionProvider>>,
+}
+
+impl ProviderRegistry {
+    pub fn register(&mut self, provider: Arc<dyn TranscriptionProvider>) {
+        println!("[PROVIDER] Registered {}", provider.id());
+        self.providers.push(provider);
+    }
+
+    /// Provider serving `model`, if any
+    pub fn resolve(&self, model: &str) -> Option<Arc<dyn TranscriptionProvider>> {
+        self.providers.iter()
+            .find(|p| p.models().iter().any(|m| m.id == model))
+            .cloned()
+    }
+
+    /// Flat model list for the settings UI, tagged with provider and capabilities
+    pub fn models(&self) -> Vec<serde_json::Value> {
+        self.providers.iter()
+            .flat_map(|p| {
+                let capabilities = p.capabilities();
+                p.models().into_iter().map(move |m| serde_json::json!({
+                    "id": m.id,
+                    "name": m.name,
+                    "provider": p.id(),
+                    "capabilities": capabilities,
+                }))
+            })
+            .collect()
+    }
+}
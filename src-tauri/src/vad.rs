@@ -0,0 +1,285 @@
+use realfft::{RealFftPlanner, RealToComplex};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// ============================================================================
+// VOICE ACTIVITY DETECTION - energy + spectral features on 20 ms frames
+// ============================================================================
+
+const SAMPLE_RATE: f32 = 16000.0;
+pub const FRAME_SAMPLES: usize = 320;          // 20 ms at 16 kHz
+const FFT_SIZE: usize = 512;
+const SPEECH_BAND_HZ: (f32, f32) = (300.0, 3400.0);
+const ANALYSIS_BAND_HZ: (f32, f32) = (80.0, 7600.0);
+const ABSOLUTE_FLOOR: f32 = 1e-7;              // -70 dBFS: never speech below this
+const INITIAL_NOISE: f32 = 1e-6;               // Prior noise floor before adaptation
+const NOISE_FALL: f32 = 0.9;                   // Track quieter backgrounds quickly
+const NOISE_RISE: f32 = 0.995;                 // Follow louder backgrounds slowly (~4 s)
+const NOISE_RISE_SPEECH: f32 = 0.9995;         // Barely move while speech is detected
+const ZCR_NOISE: f32 = 0.45;                   // Crossings per sample typical of hiss/clicks
+pub const MAX_AGGRESSIVENESS: u8 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    pub aggressiveness: u8, // 0 = keep quiet speakers .. 3 = reject most noise
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self { aggressiveness: 1 }
+    }
+}
+
+/// Per-aggressiveness thresholds, in the spirit of WebRTC's four modes
+struct Profile {
+    min_snr_db: f32,
+    min_band_ratio: f32,
+    max_flatness: f32,
+    onset_frames: u32,
+    hangover_frames: u32,
+}
+
+fn profile(aggressiveness: u8) -> Profile {
+    match aggressiveness.min(MAX_AGGRESSIVENESS) {
+        0 => Profile { min_snr_db: 3.0, min_band_ratio: 0.35, max_flatness: 0.55, onset_frames: 1, hangover_frames: 25 },
+        1 => Profile { min_snr_db: 5.0, min_band_ratio: 0.45, max_flatness: 0.45, onset_frames: 2, hangover_frames: 20 },
+        2 => Profile { min_snr_db: 7.0, min_band_ratio: 0.50, max_flatness: 0.38, onset_frames: 2, hangover_frames: 15 },
+        _ => Profile { min_snr_db: 10.0, min_band_ratio: 0.60, max_flatness: 0.30, onset_frames: 3, hangover_frames: 10 },
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct VadDecision {
+    pub is_speech: bool,
+    pub probability: f32, // Highest frame speech probability in the last call
+}
+
+pub struct Vad {
+    profile: Profile,
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    pending: Vec<f32>,
+    noise: f32,
+    speech_run: u32,
+    hangover: u32,
+    in_speech: bool,
+    last: VadDecision,
+}
+
+impl Vad {
+    pub fn new(config: VadConfig) -> Self {
+        let window = (0..FRAME_SAMPLES)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FRAME_SAMPLES as f32).cos())
+            .collect();
+        Self {
+            profile: profile(config.aggressiveness),
+            fft: RealFftPlanner::<f32>::new().plan_fft_forward(FFT_SIZE),
+            window,
+            pending: Vec::with_capacity(FRAME_SAMPLES * 2),
+            noise: INITIAL_NOISE,
+            speech_run: 0,
+            hangover: 0,
+            in_speech: false,
+            last: VadDecision::default(),
+        }
+    }
+
+    /// Swap thresholds without losing the adapted noise floor
+    pub fn set_config(&mut self, config: VadConfig) {
+        self.profile = profile(config.aggressiveness);
+    }
+
+    /// Feed any amount of 16 kHz mono audio. Speech is reported if any complete
+    /// frame in this call was inside a speech region; partial frames carry over.
+    pub fn process(&mut self, samples: &[f32]) -> VadDecision {
+        self.pending.extend_from_slice(samples);
+        if self.pending.len() < FRAME_SAMPLES {
+            return self.last;
+        }
+
+        let mut decision = VadDecision::default();
+        let frames = self.pending.len() / FRAME_SAMPLES;
+        for i in 0..frames {
+            let frame: Vec<f32> = self.pending[i * FRAME_SAMPLES..(i + 1) * FRAME_SAMPLES].to_vec();
+            let probability = self.frame_probability(&frame);
+            let speech = self.smooth(probability > 0.5);
+            decision.is_speech |= speech;
+            decision.probability = decision.probability.max(probability);
+        }
+        self.pending.drain(..frames * FRAME_SAMPLES);
+        self.last = decision;
+        decision
+    }
+
+    fn frame_probability(&mut self, frame: &[f32]) -> f32 {
+        let energy = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+        if energy < ABSOLUTE_FLOOR {
+            self.track_noise(energy.max(ABSOLUTE_FLOOR * 0.1));
+            return 0.0;
+        }
+
+        let snr_db = 10.0 * (energy / self.noise.max(ABSOLUTE_FLOOR * 0.1)).log10();
+        let (band_ratio, flatness) = self.spectral_features(frame);
+        let zcr = zero_crossing_rate(frame);
+
+        let p = &self.profile;
+        let p_snr = sigmoid(snr_db - p.min_snr_db);
+        let p_band = sigmoid((band_ratio - p.min_band_ratio) * 20.0);
+        let p_flat = sigmoid((p.max_flatness - flatness) * 20.0);
+        let zcr_penalty = if zcr > ZCR_NOISE { 0.5 } else { 1.0 };
+        let probability = p_snr * (p_band * p_flat).sqrt() * zcr_penalty;
+
+        self.track_noise(energy);
+        probability
+    }
+
+    /// Minimum-statistics style floor: falls fast, rises slowly, and almost
+    /// freezes while speech is active so long utterances don't become "noise"
+    fn track_noise(&mut self, energy: f32) {
+        let rise = if self.in_speech { NOISE_RISE_SPEECH } else { NOISE_RISE };
+        self.noise = if energy < self.noise {
+            NOISE_FALL * self.noise + (1.0 - NOISE_FALL) * energy
+        } else {
+            rise * self.noise + (1.0 - rise) * energy
+        };
+    }
+
+    /// Share of power in the speech band, and spectral flatness inside it
+    fn spectral_features(&self, frame: &[f32]) -> (f32, f32) {
+        let mut input = vec![0.0f32; FFT_SIZE];
+        for (dst, (s, w)) in input.iter_mut().zip(frame.iter().zip(&self.window)) {
+            *dst = s * w;
+        }
+        let mut spectrum = self.fft.make_output_vec();
+        if self.fft.process(&mut input, &mut spectrum).is_err() {
+            return (0.0, 1.0);
+        }
+
+        let bin_hz = SAMPLE_RATE / FFT_SIZE as f32;
+        let bin = |hz: f32| ((hz / bin_hz).round() as usize).min(spectrum.len() - 1);
+        let power: Vec<f32> = spectrum.iter().map(|c| c.norm_sqr() + 1e-12).collect();
+
+        let total: f32 = power[bin(ANALYSIS_BAND_HZ.0)..=bin(ANALYSIS_BAND_HZ.1)].iter().sum();
+        let band = &power[bin(SPEECH_BAND_HZ.0)..=bin(SPEECH_BAND_HZ.1)];
+        let band_total: f32 = band.iter().sum();
+
+        let arithmetic = band_total / band.len() as f32;
+        let geometric = (band.iter().map(|p| p.ln()).sum::<f32>() / band.len() as f32).exp();
+        (band_total / total.max(1e-12), (geometric / arithmetic).clamp(0.0, 1.0))
+    }
+
+    /// Onset needs a short run of speech frames; offset waits out the hangover
+    fn smooth(&mut self, frame_speech: bool) -> bool {
+        if frame_speech {
+            self.speech_run += 1;
+            if self.speech_run >= self.profile.onset_frames {
+                self.in_speech = true;
+                self.hangover = self.profile.hangover_frames;
+            }
+        } else {
+            self.speech_run = 0;
+            if self.hangover > 0 {
+                self.hangover -= 1;
+            } else {
+                self.in_speech = false;
+            }
+        }
+        self.in_speech
+    }
+}
+
+fn zero_crossing_rate(frame: &[f32]) -> f32 {
+    let crossings = frame.windows(2).filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0)).count();
+    crossings as f32 / frame.len() as f32
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic white noise
+    fn noise(len: usize, amplitude: f32, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..len).map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            ((state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0) * amplitude
+        }).collect()
+    }
+
+    /// Voiced-speech stand-in: 140 Hz harmonics shaped by two formants, syllable-rate envelope
+    fn voiced(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len).map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            let mut s = 0.0;
+            for k in 1..25 {
+                let f = 140.0 * k as f32;
+                let gain = (-((f - 700.0) / 300.0).powi(2)).exp() + 0.6 * (-((f - 1800.0) / 400.0).powi(2)).exp();
+                s += gain * (2.0 * std::f32::consts::PI * f * t).sin();
+            }
+            let envelope = 0.6 + 0.4 * (2.0 * std::f32::consts::PI * 4.0 * t).sin();
+            s * envelope * amplitude
+        }).collect()
+    }
+
+    fn speech_ratio(vad: &mut Vad, audio: &[f32]) -> f32 {
+        let frames: Vec<bool> = audio.chunks(FRAME_SAMPLES).map(|f| vad.process(f).is_speech).collect();
+        frames.iter().filter(|s| **s).count() as f32 / frames.len() as f32
+    }
+
+    #[test]
+    fn detects_voiced_speech_over_background() {
+        let mut vad = Vad::new(VadConfig::default());
+        speech_ratio(&mut vad, &noise(16000, 0.002, 1));
+
+        let background = noise(32000, 0.002, 2);
+        let speech: Vec<f32> = voiced(32000, 0.1).iter().zip(&background).map(|(a, b)| a + b).collect();
+        assert!(speech_ratio(&mut vad, &speech) > 0.9);
+    }
+
+    #[test]
+    fn detects_quiet_speaker() {
+        let mut vad = Vad::new(VadConfig { aggressiveness: 0 });
+        speech_ratio(&mut vad, &noise(16000, 0.0005, 3));
+
+        let background = noise(32000, 0.0005, 4);
+        let speech: Vec<f32> = voiced(32000, 0.01).iter().zip(&background).map(|(a, b)| a + b).collect();
+        assert!(speech_ratio(&mut vad, &speech) > 0.8);
+    }
+
+    #[test]
+    fn rejects_broadband_noise() {
+        for aggressiveness in 1..=MAX_AGGRESSIVENESS {
+            let mut vad = Vad::new(VadConfig { aggressiveness });
+            assert!(speech_ratio(&mut vad, &noise(48000, 0.05, 5)) < 0.1);
+        }
+    }
+
+    #[test]
+    fn rejects_low_frequency_hum() {
+        let hum: Vec<f32> = (0..48000).map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            (1..5).map(|k| (2.0 * std::f32::consts::PI * 60.0 * k as f32 * t).sin() / k as f32).sum::<f32>() * 0.05
+        }).collect();
+        let mut vad = Vad::new(VadConfig::default());
+        assert!(speech_ratio(&mut vad, &hum) < 0.1);
+    }
+
+    #[test]
+    fn silence_is_never_speech() {
+        let mut vad = Vad::new(VadConfig { aggressiveness: 0 });
+        assert_eq!(speech_ratio(&mut vad, &vec![0.0; 16000]), 0.0);
+    }
+
+    #[test]
+    fn partial_frames_carry_over() {
+        let mut vad = Vad::new(VadConfig::default());
+        let audio = voiced(16000, 0.1);
+        let detected = audio.chunks(160).map(|c| vad.process(c)).filter(|d| d.is_speech).count();
+        assert!(detected > 80);
+    }
+}
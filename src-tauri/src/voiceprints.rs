@@ -0,0 +1,312 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::diarization::{self, cosine_similarity, DiarizationState, Embedder, EMBEDDING_DIM};
+use crate::gemini_client::read_wav_16k;
+use crate::session_manager::{load_json, save_json_atomic};
+
+// ============================================================================
+// VOICEPRINTS - enrolled voices of recurring participants
+// ============================================================================
+
+const MIN_ENROLL_SECS: f32 = 5.0;
+const MAX_ENROLL_SECS: usize = 120;         // Longer files only use their start
+const MAX_SAMPLES_PER_PROFILE: usize = 10;  // Oldest sample is dropped past this
+const MAX_PROFILE_NAME_CHARS: usize = 40;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceSample {
+    pub embedding: Vec<f32>,
+    pub speech_secs: f32,
+    pub source: String, // File path, or "session" for a speaker enrolled live
+    pub enrolled_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceProfile {
+    pub id: String,
+    pub name: String, // Written into speaker_id when this voice is recognised
+    pub samples: Vec<VoiceSample>,
+    pub updated_at: String,
+}
+
+impl VoiceProfile {
+    /// Duration-weighted mean of the sample embeddings
+    pub fn voiceprint(&self) -> Vec<f32> {
+        let mut print = vec![0.0f32; EMBEDDING_DIM];
+        for sample in &self.samples {
+            for (p, e) in print.iter_mut().zip(&sample.embedding) {
+                *p += e * sample.speech_secs;
+            }
+        }
+        diarization::normalize(&mut print);
+        print
+    }
+}
+
+/// What the settings list shows; embeddings stay in the backend
+#[derive(Debug, Clone, Serialize)]
+pub struct VoiceProfileSummary {
+    pub id: String,
+    pub name: String,
+    pub samples: usize,
+    pub speech_secs: f32,
+    pub updated_at: String,
+}
+
+impl From<&VoiceProfile> for VoiceProfileSummary {
+    fn from(profile: &VoiceProfile) -> Self {
+        Self {
+            id: profile.id.clone(),
+            name: profile.name.clone(),
+            samples: profile.samples.len(),
+            speech_secs: profile.samples.iter().map(|s| s.speech_secs).sum(),
+            updated_at: profile.updated_at.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceprintStore {
+    pub profiles: Vec<VoiceProfile>,
+}
+
+impl VoiceprintStore {
+    const FILE: &'static str = "voiceprints.json";
+
+    /// Load enrolled voices, dropping samples from an older embedding format
+    pub fn load() -> Self {
+        let mut store = load_json::<Self>(Self::FILE).unwrap_or_default();
+
+        for profile in &mut store.profiles {
+            profile.samples.retain(|s| s.embedding.len() == EMBEDDING_DIM && s.speech_secs > 0.0);
+        }
+        store.profiles.retain(|p| !p.samples.is_empty());
+        store
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_json_atomic(Self::FILE, self)
+    }
+
+    /// Add a sample to the profile called `name` (case-insensitive), creating it if needed
+    pub fn enroll(&mut self, name: &str, embedding: Vec<f32>, speech_secs: f32, source: &str) -> Result<&VoiceProfile, String> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_PROFILE_NAME_CHARS {
+            return Err(format!("Name must be 1-{} characters", MAX_PROFILE_NAME_CHARS));
+        }
+        if embedding.len() != EMBEDDING_DIM {
+            return Err("Voice sample has the wrong embedding size".into());
+        }
+        if speech_secs < MIN_ENROLL_SECS {
+            return Err(format!("Need at least {:.0}s of speech to enroll (got {:.1}s)", MIN_ENROLL_SECS, speech_secs));
+        }
+
+        let now = Utc::now().to_rfc3339();
+        let index = match self.profiles.iter().position(|p| p.name.to_lowercase() == name.to_lowercase()) {
+            Some(index) => index,
+            None => {
+                self.profiles.push(VoiceProfile {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    name: name.to_string(),
+                    samples: Vec::new(),
+                    updated_at: now.clone(),
+                });
+                self.profiles.len() - 1
+            }
+        };
+
+        let profile = &mut self.profiles[index];
+        profile.samples.push(VoiceSample { embedding, speech_secs, source: source.to_string(), enrolled_at: now.clone() });
+        if profile.samples.len() > MAX_SAMPLES_PER_PROFILE {
+            profile.samples.remove(0);
+        }
+        profile.updated_at = now;
+        Ok(&self.profiles[index])
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<VoiceProfile, String> {
+        let index = self.profiles.iter().position(|p| p.id == id)
+            .ok_or_else(|| format!("Unknown voice profile: {}", id))?;
+        Ok(self.profiles.remove(index))
+    }
+
+    /// Closest enrolled voice at or above `threshold`, skipping names `taken` by other speakers
+    pub fn identify(&self, embedding: &[f32], threshold: f32, taken: impl Fn(&str) -> bool) -> Option<(&VoiceProfile, f32)> {
+        self.profiles.iter()
+            .filter(|p| !taken(&p.name))
+            .map(|p| (p, cosine_similarity(&p.voiceprint(), embedding)))
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// Embedding and voiced duration of a recording (its first two minutes), so
+/// silence padding neither passes the minimum nor weighs on the voiceprint
+fn embed_recording(embedder: &Embedder, mut samples: Vec<f32>) -> Result<(Vec<f32>, f32), String> {
+    samples.truncate(MAX_ENROLL_SECS * 16000);
+    embedder.embed_voiced(&samples)
+        .ok_or_else(|| "Not enough speech in the recording to enroll".to_string())
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn list_voice_profiles(state: tauri::State<'_, DiarizationState>) -> Result<Vec<VoiceProfileSummary>, String> {
+    let store = state.voiceprints.lock().map_err(|e| e.to_string())?;
+    Ok(store.profiles.iter().map(VoiceProfileSummary::from).collect())
+}
+
+/// Enroll a WAV recording of one person speaking (the first two minutes are used)
+#[tauri::command]
+pub async fn enroll_voice_file(
+    state: tauri::State<'_, DiarizationState>,
+    name: String,
+    path: String,
+) -> Result<VoiceProfileSummary, String> {
+    let source = path.clone();
+    let samples = tokio::task::spawn_blocking(move || read_wav_16k(&source))
+        .await
+        .map_err(|e| e.to_string())??;
+    let (embedding, secs) = embed_recording(&state.embedder, samples)?;
+
+    let mut store = state.voiceprints.lock().map_err(|e| e.to_string())?;
+    let mut updated = store.clone();
+    let profile = VoiceProfileSummary::from(updated.enroll(&name, embedding, secs, &path)?);
+    updated.save()?;
+    *store = updated;
+    println!("[VOICE] Enrolled {:.1}s of speech for {} ({} samples)", secs, profile.name, profile.samples);
+    Ok(profile)
+}
+
+/// Enroll everything heard so far from a speaker of the running session,
+/// naming them `name` (or their current label) from now on
+#[tauri::command]
+pub fn enroll_speaker_voice(
+    state: tauri::State<'_, DiarizationState>,
+    app: AppHandle,
+    speaker: String,
+    name: Option<String>,
+) -> Result<VoiceProfileSummary, String> {
+    let mut speakers = state.speakers.lock().map_err(|e| e.to_string())?;
+    let name = name.unwrap_or_else(|| speaker.clone());
+    if speakers.speakers().iter().any(|s| s.id == name.trim() && s.name.is_none()) {
+        return Err(format!("Name {} before saving their voice", speaker));
+    }
+    let (embedding, secs) = speakers.voice(&speaker)
+        .ok_or_else(|| format!("No voice recorded for {} yet", speaker))?;
+
+    let profile = {
+        let mut store = state.voiceprints.lock().map_err(|e| e.to_string())?;
+        let mut updated = store.clone();
+        let profile = VoiceProfileSummary::from(updated.enroll(&name, embedding, secs, "session")?);
+        updated.save()?;
+        *store = updated;
+        profile
+    };
+    if profile.name != speaker {
+        let (from, to) = speakers.rename(&speaker, &profile.name)?;
+        diarization::announce_rename(&app, &from, &to);
+    }
+    println!("[VOICE] Enrolled {:.1}s of {} as {}", secs, speaker, profile.name);
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn delete_voice_profile(state: tauri::State<'_, DiarizationState>, id: String) -> Result<(), String> {
+    let mut store = state.voiceprints.lock().map_err(|e| e.to_string())?;
+    let mut updated = store.clone();
+    let removed = updated.delete(&id)?;
+    updated.save()?;
+    *store = updated;
+    println!("[VOICE] Deleted profile {}", removed.name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(axis: usize) -> Vec<f32> {
+        let mut v = vec![0.0; EMBEDDING_DIM];
+        v[axis] = 1.0;
+        v
+    }
+
+    #[test]
+    fn enroll_merges_by_name_and_caps_samples() {
+        let mut store = VoiceprintStore::default();
+        assert!(store.enroll("Ali", unit(0), 2.0, "short.wav").is_err());
+        assert!(store.enroll("  ", unit(0), 10.0, "a.wav").is_err());
+
+        for _ in 0..MAX_SAMPLES_PER_PROFILE + 2 {
+            store.enroll("Ali", unit(0), 10.0, "a.wav").unwrap();
+        }
+        store.enroll("ali ", unit(0), 10.0, "b.wav").unwrap();
+        store.enroll("Sara", unit(1), 10.0, "c.wav").unwrap();
+
+        assert_eq!(store.profiles.len(), 2);
+        assert_eq!(store.profiles[0].name, "Ali");
+        assert_eq!(store.profiles[0].samples.len(), MAX_SAMPLES_PER_PROFILE);
+        assert_eq!(store.profiles[0].samples.last().unwrap().source, "b.wav");
+    }
+
+    /// Speech-like tone with a syllable envelope that never drops to a pause
+    fn speech(secs: f32) -> Vec<f32> {
+        (0..(secs * 16000.0) as usize).map(|i| {
+            let t = i as f32 / 16000.0;
+            let envelope = 0.6 + 0.4 * (2.0 * std::f32::consts::PI * 3.0 * t).sin();
+            let voice: f32 = [(140.0, 1.0), (280.0, 0.6), (700.0, 0.4), (1200.0, 0.3), (2600.0, 0.1)].iter()
+                .map(|(f, a)| a * (2.0 * std::f32::consts::PI * f * t).sin())
+                .sum();
+            0.1 * envelope * voice
+        }).collect()
+    }
+
+    #[test]
+    fn enrollment_counts_voiced_audio_not_file_length() {
+        let path = std::env::temp_dir().join(format!("god-enroll-test-{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec { channels: 1, sample_rate: 16000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for s in speech(1.0).into_iter().chain(std::iter::repeat_n(0.0, 5 * 16000)) {
+            writer.write_sample((s * 32767.0) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let embedder = Embedder::default();
+        let padded = read_wav_16k(&path.to_string_lossy()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(padded.len(), 6 * 16000);
+        let (embedding, secs) = embed_recording(&embedder, padded).unwrap();
+        assert!(secs <= 1.0, "silence counted as speech: {:.2}s", secs);
+
+        let mut store = VoiceprintStore::default();
+        assert!(store.enroll("Ali", embedding, secs, "padded.wav").is_err());
+
+        let (embedding, secs) = embed_recording(&embedder, speech(6.0)).unwrap();
+        assert!(secs > MIN_ENROLL_SECS);
+        assert!(store.enroll("Ali", embedding, secs, "speech.wav").is_ok());
+    }
+
+    #[test]
+    fn identify_needs_threshold_and_skips_taken_names() {
+        let mut store = VoiceprintStore::default();
+        store.enroll("Ali", unit(0), 10.0, "a.wav").unwrap();
+        store.enroll("Sara", unit(1), 10.0, "b.wav").unwrap();
+
+        let mut near_ali = unit(0);
+        near_ali[1] = 0.3;
+        diarization::normalize(&mut near_ali);
+        let (profile, similarity) = store.identify(&near_ali, 0.9, |_| false).unwrap();
+        assert_eq!(profile.name, "Ali");
+        assert!(similarity > 0.95);
+
+        assert!(store.identify(&near_ali, 0.99, |_| false).is_none());
+        assert!(store.identify(&near_ali, 0.2, |name| name == "Ali").unwrap().0.name == "Sara");
+        assert!(store.identify(&unit(2), 0.5, |_| false).is_none());
+    }
+}
@@ -0,0 +1,145 @@
+use futures_util::future::BoxFuture;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+use crate::session_manager;
+use crate::transcription::{
+    ProviderCapabilities, ProviderError, ProviderModel, Transcription, TranscriptionProvider,
+    TranscriptionRequest,
+};
+
+// ============================================================================
+// WHISPER PROVIDER - offline transcription, audio never leaves the machine
+// ============================================================================
+
+const MODEL_PREFIX: &str = "whisper:";
+
+type LoadedModel = Arc<Mutex<Option<(String, Arc<WhisperContext>)>>>;
+
+/// Runs ggml Whisper models found in the local models directory on the CPU.
+/// Output is plain text; categories come from the engine's keyword tagging.
+#[derive(Default)]
+pub struct WhisperProvider {
+    loaded: LoadedModel, // Last used model stays in memory
+}
+
+/// `ggml-*.bin` files are picked up from here, e.g. ggml-base.en.bin
+pub fn models_dir() -> Option<PathBuf> {
+    session_manager::data_dir().map(|d| d.join("models"))
+}
+
+impl WhisperProvider {
+    /// Loading takes seconds for larger models, so this only runs on blocking threads
+    fn context(loaded: &LoadedModel, model: &str) -> Result<Arc<WhisperContext>, ProviderError> {
+        let mut loaded = loaded.lock().unwrap();
+        if let Some((id, ctx)) = loaded.as_ref() {
+            if id == model {
+                return Ok(ctx.clone());
+            }
+        }
+
+        let file = model.strip_prefix(MODEL_PREFIX)
+            .ok_or_else(|| ProviderError::Failed(format!("Not a Whisper model: {}", model)))?;
+        let path = models_dir()
+            .map(|d| d.join(format!("{}.bin", file)))
+            .filter(|p| p.is_file())
+            .ok_or_else(|| ProviderError::Failed(format!("Model file missing: {}.bin", file)))?;
+
+        println!("[WHISPER] Loading {}", path.display());
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(false);
+        let ctx = WhisperContext::new_with_params(&path.to_string_lossy(), params)
+            .map_err(|e| ProviderError::Failed(format!("Failed to load {}: {}", path.display(), e)))?;
+
+        let ctx = Arc::new(ctx);
+        *loaded = Some((model.to_string(), ctx.clone()));
+        Ok(ctx)
+    }
+
+    fn run(ctx: &WhisperContext, audio: &[f32]) -> Result<String, ProviderError> {
+        let mut state = ctx.create_state()
+            .map_err(|e| ProviderError::Failed(format!("Whisper state: {}", e)))?;
+
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get()).min(8);
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(threads as i32);
+        params.set_language(Some("auto"));
+        params.set_no_context(true);
+        params.set_suppress_blank(true);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_special(false);
+        params.set_print_timestamps(false);
+
+        state.full(params, audio)
+            .map_err(|e| ProviderError::Failed(format!("Whisper: {}", e)))?;
+
+        let segments = state.full_n_segments()
+            .map_err(|e| ProviderError::Failed(format!("Whisper: {}", e)))?;
+        let mut text = String::new();
+        for i in 0..segments {
+            if let Ok(segment) = state.full_get_segment_text_lossy(i) {
+                text.push_str(segment.trim());
+                text.push(' ');
+            }
+        }
+        Ok(text.trim().to_string())
+    }
+}
+
+impl TranscriptionProvider for WhisperProvider {
+    fn id(&self) -> &'static str {
+        "whisper"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            structured_output: false,
+            requires_api_key: false,
+            offline: true,
+            max_segment_secs: 600.0,
+        }
+    }
+
+    fn models(&self) -> Vec<ProviderModel> {
+        let Some(dir) = models_dir() else { return Vec::new() };
+        let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+        let mut models: Vec<ProviderModel> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "bin"))
+            .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+            .filter(|stem| stem.starts_with("ggml-"))
+            .map(|stem| ProviderModel {
+                name: format!("🔒 Whisper {} (offline)", stem.trim_start_matches("ggml-")),
+                id: format!("{}{}", MODEL_PREFIX, stem),
+            })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        models
+    }
+
+    fn transcribe<'a>(&'a self, request: TranscriptionRequest<'a>) -> BoxFuture<'a, Result<Transcription, ProviderError>> {
+        let loaded = self.loaded.clone();
+        let model = request.model.to_string();
+        let audio = request.audio.to_vec();
+        Box::pin(async move {
+            let text = tokio::task::spawn_blocking(move || Self::run(&Self::context(&loaded, &model)?, &audio))
+                .await
+                .map_err(|e| ProviderError::Failed(e.to_string()))??;
+            Ok(Transcription { text, ..Default::default() })
+        })
+    }
+
+    fn check<'a>(&'a self, _api_key: &'a str, model: &'a str) -> BoxFuture<'a, Result<(), ProviderError>> {
+        let loaded = self.loaded.clone();
+        let model = model.to_string();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || Self::context(&loaded, &model).map(|_| ()))
+                .await
+                .map_err(|e| ProviderError::Failed(e.to_string()))?
+        })
+    }
+}